@@ -3,7 +3,9 @@
 
 use std::path::PathBuf;
 
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Parser, Subcommand};
+
+use crate::{options::CaseMode, stats};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -13,8 +15,17 @@ use clap::{ArgGroup, Parser};
         .args(&["exec", "exec_with"]),
 ))]
 pub(crate) struct Args {
-    /// Configuration file path.
-    pub(crate) config: PathBuf,
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    /// Configuration file, directory, or glob pattern, e.g. `~/.config/fr33zmenu/*.toml`.
+    /// Defaults to `$XDG_CONFIG_HOME/fr33zmenu/config.toml`, then `/etc/fr33zmenu`.
+    pub(crate) config: Option<PathBuf>,
+
+    /// A file whose theme overrides the theme of the config, so that it can be shared by several
+    /// configs. It's also layered over the `theme_file` of the config.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) theme: Option<PathBuf>,
 
     /// Execute the selection.
     #[arg(short = 'x', long)]
@@ -24,7 +35,161 @@ pub(crate) struct Args {
     #[arg(short = 'w', long, value_name = "CMD")]
     pub(crate) exec_with: Option<String>,
 
-    /// Exit the program if focus is lost.
+    /// Write the selection to a file instead of stdout. The file is replaced atomically.
+    #[arg(short, long, value_name = "PATH", conflicts_with_all = ["execute", "session_chooser"])]
+    pub(crate) output_file: Option<PathBuf>,
+
+    /// Append the selection to the output file instead of replacing it.
+    #[arg(short, long, requires = "output_file", conflicts_with = "execute")]
+    pub(crate) append: bool,
+
+    /// Write the selection to a Unix socket or named pipe instead of stdout, e.g. one read by a
+    /// status bar or daemon.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["execute", "session_chooser", "output_file"]
+    )]
+    pub(crate) output_socket: Option<PathBuf>,
+
+    /// Keep the menu open after executing a selection, and show the outcome of recent
+    /// executions.
+    #[arg(short, long, requires = "execute")]
+    pub(crate) stay_open: bool,
+
+    /// Choose a login session from the installed Wayland and X sessions, and replace the
+    /// program with it. Suitable for running from getty; the exit keybinds are disabled.
+    #[arg(long, conflicts_with_all = ["execute", "stay_open", "transient"])]
+    pub(crate) session_chooser: bool,
+
+    /// Reload the config while the menu is open whenever any of its files change.
+    #[arg(long, conflicts_with = "session_chooser")]
+    pub(crate) watch: bool,
+
+    /// Start on the menu with this name, instead of the first tab or the one that was last open.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["session_chooser", "dmenu"])]
+    pub(crate) menu: Option<String>,
+
+    /// Start with this text in the input, e.g. so that a script can open the menu pre-filtered.
+    #[arg(short, long)]
+    pub(crate) query: Option<String>,
+
+    /// How letter case is treated when matching the input. Overrides the `case` option.
+    #[arg(short, long, value_enum)]
+    pub(crate) case: Option<CaseMode>,
+
+    /// Exit the program if focus is lost. Not all terminals report focus changes; see
+    /// `--auto-close-secs` for an alternative.
     #[arg(short, long)]
     pub(crate) transient: bool,
+
+    /// Exit the program after this many seconds without a key press.
+    #[arg(long, value_name = "SECS", conflicts_with = "session_chooser")]
+    pub(crate) auto_close_secs: Option<u64>,
+
+    /// Only use the cached copies of remote includes, without accessing the network.
+    #[arg(long)]
+    pub(crate) offline: bool,
+
+    /// Print how long each startup phase took, and percentiles of how long key presses took to
+    /// handle and draw, to stderr on exit.
+    #[arg(long)]
+    pub(crate) profile: bool,
+
+    /// If another instance was opened with this flag and is still open, ask it to raise its
+    /// window and exit, instead of opening a second menu.
+    #[arg(long, conflicts_with = "session_chooser")]
+    pub(crate) single_instance: bool,
+
+    /// Hide entry values, mask entries marked `private`, and don't write to the history, for
+    /// screen recordings and screenshots.
+    #[arg(long)]
+    pub(crate) demo: bool,
+
+    /// The terminal to draw the interface on. Defaults to the controlling terminal, `/dev/tty`.
+    /// Only the output goes there: keys are still read from stdin if it's a terminal, or else
+    /// from `/dev/tty`.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) tty: Option<PathBuf>,
+
+    /// Draw the interface without colors, using only text attributes such as bold. Also enabled
+    /// by setting `NO_COLOR`.
+    #[arg(long)]
+    pub(crate) no_color: bool,
+
+    /// Show the lines read from stdin as the entries, and print the selected line, like dmenu.
+    /// The menus in the config are ignored.
+    #[arg(long, conflicts_with_all = ["session_chooser", "watch"])]
+    pub(crate) dmenu: bool,
+
+    /// The prompt in dmenu mode.
+    #[arg(short, long, default_value = "> ", requires = "dmenu")]
+    pub(crate) prompt: String,
+
+    /// A message shown under the prompt in dmenu mode.
+    #[arg(long, requires = "dmenu")]
+    pub(crate) mesg: Option<String>,
+
+    /// Parse the entries and the message as markup in dmenu mode, e.g. `<b>bold</b>` or
+    /// `<span foreground="red">red</span>`. The selected line is printed with its markup.
+    #[arg(long, requires = "dmenu")]
+    pub(crate) markup: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+    /// Validate the config and print a report of any problems, without starting the interface.
+    /// Exits unsuccessfully if any errors are found.
+    Check {
+        /// Configuration file, directory, or glob pattern. Defaults to the same paths as when
+        /// starting the interface.
+        config: Option<PathBuf>,
+    },
+
+    /// Write a starter config with example menus and the default options, keybinds, and theme.
+    Init {
+        /// Where to write the config. Defaults to `$XDG_CONFIG_HOME/fr33zmenu/config.toml`.
+        config: Option<PathBuf>,
+
+        /// Overwrite the file if it already exists.
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Open the menu in a new terminal window, floating in the center of the screen like a
+    /// graphical launcher, with the terminal and size from the `[popup]` section of the config.
+    Popup {
+        /// Configuration file, directory, or glob pattern. Defaults to the same paths as when
+        /// starting the interface.
+        config: Option<PathBuf>,
+
+        /// Arguments passed to the menu, after `--`, e.g. `-- --exec --transient`.
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    /// Execute entries without starting the interface, e.g. from a keybind or a script.
+    /// Entries are executed with `--exec-with` if it's given, or else as with `--exec`.
+    Run {
+        /// Configuration file, directory, or glob pattern.
+        config: PathBuf,
+
+        /// The entries to execute in order, written as `<menu>/<entry>`.
+        #[arg(required = true, value_name = "MENU/ENTRY")]
+        entries: Vec<String>,
+    },
+
+    /// Wait for a key chord to be pressed, and print it as it's written in the keybinds config.
+    RecordKeybind,
+
+    /// Print usage statistics from the history of submitted entries.
+    Stats {
+        /// Only include the menus defined in this config. Defaults to the same paths as when
+        /// starting the interface, or every menu if no config exists.
+        config: Option<PathBuf>,
+
+        /// The format to print the statistics in.
+        #[arg(short, long, value_enum, default_value_t)]
+        format: stats::Format,
+    },
 }
@@ -0,0 +1,317 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Validates configuration without starting the interface.
+
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    config::{self, Config, EntryKind, Menu},
+    keybinds::Keybind,
+    layout::LayoutOptions,
+    options::Options,
+    popup::PopupOptions,
+    sources,
+    theme::ThemeStyle,
+};
+
+/// How serious a problem is. Errors cause the check to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in the config.
+#[derive(Debug)]
+pub(crate) struct Problem {
+    /// How serious the problem is.
+    pub(crate) severity: Severity,
+
+    /// Where the problem is, e.g. `menus.power`.
+    pub(crate) location: String,
+
+    /// What the problem is.
+    pub(crate) message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}: {}: {}", self.location, self.message)
+    }
+}
+
+/// Loads the config at the provided path (see [config::config_path]), and prints a report of
+/// the problems found in it, with the theme of the `theme` file layered over it. Returns `false`
+/// if any errors were found.
+pub(crate) fn check(path: Option<PathBuf>, theme: Option<&Path>) -> Result<bool> {
+    let path = config::config_path(path)?;
+    println!("Checking {}", path.display());
+
+    let problems = match config::load_config(Some(path.clone()), theme) {
+        Ok(config) => problems(&config),
+        Err(e) => {
+            let mut problems = invalid_values(path, theme);
+            if problems.is_empty() {
+                problems.push(Problem {
+                    severity: Severity::Error,
+                    location: "config".to_string(),
+                    message: format!("{e:#}"),
+                });
+            }
+            problems
+        }
+    };
+
+    let problems: Vec<Problem> = problems.into_iter().chain(environment_problems()).collect();
+    for problem in &problems {
+        println!("{problem}");
+    }
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    let warnings = problems.len() - errors;
+    println!("{errors} error(s), {warnings} warning(s)");
+    Ok(errors == 0)
+}
+
+/// Terminals (by `$TERM`) that are known not to report focus changes.
+const NO_FOCUS_EVENTS: &[&str] = &["linux", "dumb", "vt100", "vt220", "cons25"];
+
+/// Finds problems with the environment that the interface would run in.
+fn environment_problems() -> Vec<Problem> {
+    let term = env::var("TERM").unwrap_or_default();
+    if term.is_empty() || NO_FOCUS_EVENTS.contains(&term.as_str()) {
+        vec![Problem {
+            severity: Severity::Warning,
+            location: "environment".to_string(),
+            message: format!(
+                "terminal '{term}' doesn't report focus changes, so --transient has no effect; \
+                 use --auto-close-secs instead"
+            ),
+        }]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Finds the values that prevent a config from being deserialized, such as invalid keybinds and
+/// theme colors, by deserializing each value on its own.
+fn invalid_values(path: PathBuf, theme: Option<&Path>) -> Vec<Problem> {
+    let raw = match config::read_config(Some(path), theme) {
+        Ok(raw) => raw,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut problems = Vec::new();
+    invalid_table::<ThemeStyle>(&raw, "theme", &mut problems);
+    invalid_table::<Vec<Keybind>>(&raw, "keybinds", &mut problems);
+    invalid_table::<Menu>(&raw, "menus", &mut problems);
+    if let Err(e) = raw.get::<Options>("options") {
+        problems.push(Problem {
+            severity: Severity::Error,
+            location: "options".to_string(),
+            message: e.to_string(),
+        });
+    }
+    if let Err(e) = raw.get::<LayoutOptions>("layout") {
+        problems.push(Problem {
+            severity: Severity::Error,
+            location: "layout".to_string(),
+            message: e.to_string(),
+        });
+    }
+    if let Err(e) = raw.get::<PopupOptions>("popup") {
+        problems.push(Problem {
+            severity: Severity::Error,
+            location: "popup".to_string(),
+            message: e.to_string(),
+        });
+    }
+    problems
+}
+
+/// Deserializes each value in a table as `T`, recording the values that fail.
+fn invalid_table<T: DeserializeOwned>(
+    raw: &::config::Config,
+    table: &str,
+    problems: &mut Vec<Problem>,
+) {
+    let mut keys: Vec<String> = raw
+        .get_table(table)
+        .unwrap_or_default()
+        .into_keys()
+        .collect();
+    keys.sort();
+    for key in keys {
+        let location = format!("{table}.{key}");
+        if let Err(e) = raw.get::<T>(&location) {
+            problems.push(Problem {
+                severity: Severity::Error,
+                location,
+                message: e.to_string(),
+            });
+        }
+    }
+}
+
+/// Finds problems in a config that deserialized successfully.
+fn problems(config: &Config) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if config.menus.is_empty() {
+        problems.push(Problem {
+            severity: Severity::Error,
+            location: "menus".to_string(),
+            message: "no menus are defined".to_string(),
+        });
+    }
+    if config.options.transliterate && !cfg!(feature = "transliteration") {
+        problems.push(Problem {
+            severity: Severity::Warning,
+            location: "options.transliterate".to_string(),
+            message: "fr33zmenu was built without the transliteration feature, so this has no \
+                      effect"
+                .to_string(),
+        });
+    }
+    if config.options.remote_includes && !cfg!(feature = "remote") {
+        problems.push(Problem {
+            severity: Severity::Warning,
+            location: "options.remote_includes".to_string(),
+            message: "fr33zmenu was built without the remote feature, so this has no effect"
+                .to_string(),
+        });
+    }
+    for (name, menu) in &config.menus {
+        if menu.entries.is_empty() {
+            problems.push(Problem {
+                severity: Severity::Warning,
+                location: format!("menus.{name}"),
+                message: "menu has no entries".to_string(),
+            });
+        }
+        let capabilities = menu
+            .source
+            .as_deref()
+            .and_then(sources::provider)
+            .map(|provider| provider.capabilities())
+            .unwrap_or_default();
+        let unread = [
+            ("path", menu.path.is_some() && !capabilities.path),
+            (
+                "terminal",
+                menu.terminal.is_some() && !capabilities.terminal,
+            ),
+        ];
+        for (setting, _) in unread.iter().filter(|(_, unread)| *unread) {
+            problems.push(Problem {
+                severity: Severity::Warning,
+                location: format!("menus.{name}.{setting}"),
+                message: match &menu.source {
+                    Some(source) => {
+                        format!("source '{source}' doesn't use this, so it has no effect")
+                    }
+                    None => "only sources use this, but the menu has no source".to_string(),
+                },
+            });
+        }
+        for (entry_name, entry) in &menu.entries {
+            let target = &entry.value;
+            if entry.kind == EntryKind::Submenu && !config.menus.iter().any(|m| m.0 == *target) {
+                problems.push(Problem {
+                    severity: Severity::Error,
+                    location: format!("menus.{name}.entries.{entry_name}"),
+                    message: format!("submenu '{target}' doesn't exist"),
+                });
+            }
+        }
+        let mut prefer: Vec<_> = menu.prefer.iter().collect();
+        prefer.sort();
+        for (query, target) in prefer {
+            if !menu
+                .entries
+                .iter()
+                .any(|(entry_name, _)| entry_name == target)
+            {
+                problems.push(Problem {
+                    severity: Severity::Warning,
+                    location: format!("menus.{name}.prefer.{query}"),
+                    message: format!("entry '{target}' doesn't exist"),
+                });
+            }
+        }
+        if menu.hotkeys {
+            for (i, (entry_name, entry)) in menu.entries.iter().enumerate() {
+                let other = menu.entries[..i]
+                    .iter()
+                    .find(|(_, other)| entry.key.is_some() && other.key == entry.key);
+                if let (Some(key), Some((other_name, _))) = (entry.key, other) {
+                    problems.push(Problem {
+                        severity: Severity::Error,
+                        location: format!("menus.{name}.entries.{entry_name}"),
+                        message: format!("key '{key}' is also used by '{other_name}'"),
+                    });
+                }
+            }
+        }
+    }
+
+    // Every keybind can only be bound to one command
+    let actions = config.keybinds.actions();
+    for (i, (action, keybinds)) in actions.iter().enumerate() {
+        for (j, keybind) in keybinds.iter().enumerate() {
+            if keybinds[..j].contains(keybind) {
+                problems.push(Problem {
+                    severity: Severity::Warning,
+                    location: format!("keybinds.{action}"),
+                    message: format!("'{keybind}' is listed more than once"),
+                });
+            }
+            for (other, other_keybinds) in &actions[i + 1..] {
+                if other_keybinds.contains(keybind) {
+                    problems.push(Problem {
+                        severity: Severity::Error,
+                        location: format!("keybinds.{action}"),
+                        message: format!("'{keybind}' is also bound to '{other}'"),
+                    });
+                }
+            }
+        }
+    }
+
+    // Custom actions are matched before the keybinds, so they hide any command on the same key
+    let mut custom_actions: Vec<_> = config.actions.iter().collect();
+    custom_actions.sort_by_key(|(name, _)| *name);
+    for (i, (name, custom_action)) in custom_actions.iter().enumerate() {
+        let keybind = &custom_action.keybind;
+        if let Some((action, _)) = actions.iter().find(|(_, kbs)| kbs.contains(keybind)) {
+            problems.push(Problem {
+                severity: Severity::Warning,
+                location: format!("actions.{name}"),
+                message: format!("'{keybind}' hides the '{action}' keybind"),
+            });
+        }
+        if let Some((other, _)) = custom_actions[..i]
+            .iter()
+            .find(|(_, a)| a.keybind == *keybind)
+        {
+            problems.push(Problem {
+                severity: Severity::Error,
+                location: format!("actions.{name}"),
+                message: format!("'{keybind}' is also bound to action '{other}'"),
+            });
+        }
+    }
+
+    problems
+}
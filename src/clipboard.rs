@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Reads and writes the system clipboard with the clipboard tools of the display server.
+//!
+//! Terminals aren't asked for the clipboard with OSC 52, since few of them answer the query, and
+//! the answer would arrive mixed in with the key events. Writing with OSC 52 is more widely
+//! supported, including over SSH, so it's used to copy when no clipboard tool is available.
+
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+
+/// Commands that print the clipboard, in order of preference, after the environment variable that
+/// indicates that their display server is running.
+const PASTE_COMMANDS: &[(&str, &str, &[&str])] = &[
+    ("WAYLAND_DISPLAY", "wl-paste", &["--no-newline"]),
+    ("DISPLAY", "xclip", &["-selection", "clipboard", "-out"]),
+    ("DISPLAY", "xsel", &["--clipboard", "--output"]),
+];
+
+/// Commands that replace the clipboard with their input, in order of preference, after the
+/// environment variable that indicates that their display server is running.
+const COPY_COMMANDS: &[(&str, &str, &[&str])] = &[
+    ("WAYLAND_DISPLAY", "wl-copy", &[]),
+    ("DISPLAY", "xclip", &["-selection", "clipboard", "-in"]),
+    ("DISPLAY", "xsel", &["--clipboard", "--input"]),
+];
+
+/// Returns the contents of the clipboard, from the first clipboard tool that's installed for the
+/// running display server.
+pub(crate) fn paste() -> Result<String> {
+    for (var, program, args) in PASTE_COMMANDS {
+        if env::var_os(var).is_none() {
+            continue;
+        }
+        let Ok(output) = Command::new(program).args(*args).output() else {
+            continue; // Not installed
+        };
+        if !output.status.success() {
+            bail!("{program} exited with {}", output.status);
+        }
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    bail!("No clipboard tool found; install wl-clipboard, xclip, or xsel")
+}
+
+/// Replaces the contents of the clipboard with text, using the first clipboard tool that's
+/// installed for the running display server, or else asking the terminal to with OSC 52.
+pub(crate) fn copy(tty: &mut impl Write, text: &str) -> Result<()> {
+    for (var, program, args) in COPY_COMMANDS {
+        if env::var_os(var).is_none() {
+            continue;
+        }
+        // The tools keep running in the background to serve the clipboard, so their output
+        // mustn't be waited on
+        let spawned = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = spawned else {
+            continue; // Not installed
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .with_context(|| format!("Failed to write to {program}"))?;
+        }
+        let status = child.wait()?;
+        if !status.success() {
+            bail!("{program} exited with {status}");
+        }
+        return Ok(());
+    }
+    osc52(tty, text).context("Failed to copy with OSC 52")
+}
+
+/// Asks the terminal to replace the clipboard with text. Terminals that don't support it ignore
+/// the request, so success doesn't mean that the text was copied.
+fn osc52(tty: &mut impl Write, text: &str) -> io::Result<()> {
+    write!(tty, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    tty.flush()
+}
+
+/// Encodes bytes as standard base64, with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (u32::from(byte) << (16 - 8 * i)));
+        // Each byte of the chunk spans two characters, and missing bytes are padded
+        for i in 0..=chunk.len() {
+            encoded.push(char::from(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize]));
+        }
+        for _ in chunk.len()..3 {
+            encoded.push('=');
+        }
+    }
+    encoded
+}
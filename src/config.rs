@@ -1,21 +1,181 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 //! Loads and globs configuration files.
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
+};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 
 use serde::Deserialize;
-use serde_with::serde_as;
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
 
-use crate::{keybinds::Keybinds, theme::Theme};
+use crate::{
+    keybinds::{Keybind, Keybinds},
+    layout::LayoutOptions,
+    markup,
+    options::{MatchMode, Options},
+    palette, profile, remote, sources,
+    status::{self, Status},
+    theme::Theme,
+    util, vars,
+};
 
-static DEFAULT_THEME: &'static str = include_str!("../config/theme.default.toml");
-static DEFAULT_KEYBINDS: &'static str = include_str!("../config/keybinds.default.toml");
+pub(crate) static DEFAULT_THEME: &str = include_str!("../config/theme.default.toml");
+pub(crate) static DEFAULT_KEYBINDS: &str = include_str!("../config/keybinds.default.toml");
+pub(crate) static DEFAULT_OPTIONS: &str = include_str!("../config/options.default.toml");
+pub(crate) static DEFAULT_LAYOUT: &str = include_str!("../config/layout.default.toml");
+pub(crate) static DEFAULT_POPUP: &str = include_str!("../config/popup.default.toml");
+
+/// Config path used if no user config exists.
+const SYSTEM_CONFIG: &str = "/etc/fr33zmenu";
+
+/// Supported config file extensions, and their formats.
+const CONFIG_FORMATS: &[(&str, config::FileFormat)] = &[
+    ("toml", config::FileFormat::Toml),
+    ("yaml", config::FileFormat::Yaml),
+    ("yml", config::FileFormat::Yaml),
+    ("json", config::FileFormat::Json),
+    ("json5", config::FileFormat::Json5),
+    ("ron", config::FileFormat::Ron),
+    ("ini", config::FileFormat::Ini),
+];
+
+/// The kind of a menu entry.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EntryKind {
+    /// An entry that can be matched and submitted.
+    #[default]
+    Normal,
+
+    /// A horizontal rule. Can't be matched or submitted.
+    Separator,
+
+    /// Free text, i.e. the entry's name. Can't be matched or submitted.
+    Label,
+
+    /// Opens the menu named by the entry's value when submitted.
+    Submenu,
+
+    /// Opens the entry's value, a URL, with the `opener` option when submitted, whether or not
+    /// the selection is executed.
+    Link,
+}
+
+impl EntryKind {
+    /// Indicates that entries of this kind can be matched and submitted.
+    pub(crate) fn is_submittable(self) -> bool {
+        matches!(
+            self,
+            EntryKind::Normal | EntryKind::Submenu | EntryKind::Link
+        )
+    }
+}
+
+/// A menu entry. May be written as just its value, or as a table.
+#[derive(Debug, Default, Clone, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct Entry {
+    /// The kind of entry.
+    #[serde(rename = "type")]
+    pub(crate) kind: EntryKind,
+
+    /// The sorting order, used while the input is empty.
+    pub(crate) order: i64,
+
+    /// The value output or executed when the entry is submitted. May also be written as `cmd`.
+    #[serde(alias = "cmd")]
+    pub(crate) value: String,
+
+    /// A description shown between the entry's name and value.
+    pub(crate) desc: Option<String>,
+
+    /// A glyph, such as an emoji or a Nerd Font icon, shown before the entry's name.
+    pub(crate) icon: Option<String>,
+
+    /// Indicates that the entry is informational, and can't be submitted.
+    pub(crate) info: bool,
+
+    /// Indicates that the entry is left out of the menu, without removing it from the config.
+    pub(crate) disabled: bool,
+
+    /// Indicates that the entry's name and description are masked with `--demo`.
+    pub(crate) private: bool,
+
+    /// The number of characters that must be typed before the entry is shown. Overrides the
+    /// menu's `min_query`.
+    pub(crate) min_query: Option<usize>,
+
+    /// The key that submits the entry in a hotkey menu. Assigned automatically if not provided.
+    pub(crate) key: Option<char>,
+
+    /// Seconds after which the entry's command is killed if it's still running, with
+    /// `--stay-open`.
+    pub(crate) timeout_secs: Option<u64>,
+
+    /// A shell command run when the config is loaded, whose exit status selects the style of the
+    /// entry's value. See [Status].
+    pub(crate) status_style_cmd: Option<String>,
+
+    /// The status from the entry's `status_style_cmd`.
+    #[serde(skip)]
+    pub(crate) status: Option<Status>,
+
+    /// Indicates that the entry was generated by the menu's source.
+    #[serde(skip)]
+    pub(crate) generated: bool,
+
+    /// The menu that the entry was copied from, for entries in the recent menu.
+    #[serde(skip)]
+    pub(crate) origin: Option<String>,
+
+    /// How often and how recently the entry was submitted, if the `frecency` option is set. See
+    /// [history::frecency](crate::history::frecency).
+    #[serde(skip)]
+    pub(crate) frecency: u64,
+
+    /// The formatting of the name, parsed from markup with `--markup`.
+    #[serde(skip)]
+    pub(crate) markup: Vec<markup::Span>,
+}
+
+impl From<String> for Entry {
+    fn from(value: String) -> Self {
+        Entry {
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+impl FromStr for Entry {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Entry::from(s.to_string()))
+    }
+}
+
+/// Translations of a menu's prompt and entry names into a locale.
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub(crate) struct Translation {
+    /// The translated prompt.
+    pub(crate) prompt: Option<String>,
+
+    /// Translated entry names, keyed by the original names.
+    pub(crate) entries: HashMap<String, String>,
+}
 
 /// A menu page.
 #[serde_as]
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Deserialize, PartialEq, Eq)]
 pub(crate) struct Menu {
     /// The sorting order.
     #[serde(default)]
@@ -24,9 +184,90 @@ pub(crate) struct Menu {
     /// The input prompt.
     pub(crate) prompt: String,
 
+    /// Generates additional entries from a built-in source, e.g. `desktop`.
+    #[serde(default)]
+    pub(crate) source: Option<String>,
+
+    /// File read by the menu's source, e.g. an `.ics` calendar for `agenda`.
+    #[serde(default)]
+    pub(crate) path: Option<PathBuf>,
+
+    /// Command prefix used to run generated entries that require a terminal.
+    #[serde(default)]
+    pub(crate) terminal: Option<String>,
+
+    /// Seconds between regenerating the menu's entries while it's open.
+    #[serde(default)]
+    pub(crate) refresh: Option<u64>,
+
+    /// Indicates that pressing an entry's key submits it while the input is empty.
+    #[serde(default)]
+    pub(crate) hotkeys: bool,
+
+    /// Indicates that the input is matched against entry values as well as names. Overrides the
+    /// `match_values` option.
+    #[serde(default)]
+    pub(crate) match_values: Option<bool>,
+
+    /// How the input is matched against entries. Overrides the `match_mode` option.
+    #[serde(default)]
+    pub(crate) match_mode: Option<MatchMode>,
+
+    /// Indicates that entry values are shown. Overrides the `show_values` option.
+    #[serde(default)]
+    pub(crate) show_values: Option<bool>,
+
+    /// The number of entries shown side by side in each row. Overrides the `columns` option.
+    #[serde(default)]
+    pub(crate) columns: Option<usize>,
+
+    /// The command whose output is shown in the preview pane. Overrides the `preview_command`
+    /// option.
+    #[serde(default)]
+    pub(crate) preview_command: Option<String>,
+
+    /// The number of characters that must be typed before the menu's entries are shown.
+    #[serde(default)]
+    pub(crate) min_query: usize,
+
+    /// Entries ranked first while the input starts with a query, regardless of how well they
+    /// match, keyed by the query. Entries are given by name.
+    #[serde(default)]
+    pub(crate) prefer: HashMap<String, String>,
+
+    /// Indicates that the menu is only opened through submenu entries, and isn't shown as a tab.
+    #[serde(default)]
+    pub(crate) submenu: bool,
+
+    /// Indicates that the menu is left out, without removing it from the config.
+    #[serde(default)]
+    pub(crate) disabled: bool,
+
+    /// Translations of the prompt and entry names, keyed by locale, e.g. `de` or `pt_BR`.
+    #[serde(default)]
+    pub(crate) locales: HashMap<String, Translation>,
+
+    /// When the menu's entries were last generated.
+    #[serde(skip)]
+    pub(crate) generated_at: Option<Instant>,
+
     /// The menu's entries. The key is used as the entry name.
-    #[serde_as(as = "HashMap<_, _>")]
-    pub(crate) entries: Vec<(String, String)>,
+    #[serde_as(as = "HashMap<_, PickFirst<(_, DisplayFromStr)>>")]
+    #[serde(default)]
+    pub(crate) entries: Vec<(String, Entry)>,
+}
+
+/// A way to submit entries besides the submit keybind, set in the `[actions]` table.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CustomAction {
+    /// The key that submits the selected entry with this action.
+    pub(crate) keybind: Keybind,
+
+    /// The command that the selection is executed with, overriding `--exec` and `--exec-with`.
+    /// If it isn't set, the selection is submitted as usual, but output after the action's name
+    /// and a tab, so that a script can tell how it was submitted.
+    #[serde(default)]
+    pub(crate) exec_with: Option<String>,
 }
 
 /// A configuration file.
@@ -38,15 +279,135 @@ pub(crate) struct Config {
 
     /// Pages of entries. The key is used as the menu name.
     #[serde_as(as = "HashMap<_, _>")]
+    #[serde(default)]
     pub(crate) menus: Vec<(String, Menu)>,
 
     /// Keybinds used to interact with the interface.
     pub(crate) keybinds: Keybinds,
+
+    /// Settings that change how the interface behaves.
+    pub(crate) options: Options,
+
+    /// The rows and spacing of the interface.
+    pub(crate) layout: LayoutOptions,
+
+    /// Extra ways to submit entries. The key is used as the action name.
+    #[serde(default)]
+    pub(crate) actions: HashMap<String, CustomAction>,
+}
+
+/// Loads the config files at the provided path, and combines them with the defaults. See
+/// [config_files] for how the path is resolved. If no path is provided, only the defaults are
+/// loaded. The theme of the `theme` file, given with `--theme`, is layered over them.
+pub(crate) fn load_config(path: Option<PathBuf>, theme: Option<&Path>) -> Result<Config> {
+    let start = Instant::now();
+    let mut config = read_config(path, theme)?
+        .try_deserialize::<Config>()
+        .context("Failed to deserialize config")?;
+    profile::phase("config parse", start);
+    // Disabled menus and entries are still deserialized, so they're validated
+    config.menus.retain(|(_, menu)| !menu.disabled);
+    let locale = locale();
+    for (name, menu) in config.menus.iter_mut() {
+        menu.entries.retain(|(_, entry)| !entry.disabled);
+        if let Some(locale) = &locale {
+            localize_menu(menu, locale);
+        }
+        expand_menu(menu);
+        link_icons(menu, &config.options.link_icon);
+        let start = Instant::now();
+        sources::generate(name, menu)?;
+        profile::phase("source generation", start);
+        util::assign_hotkeys(menu);
+    }
+    let start = Instant::now();
+    status::probe(config.menus.iter_mut().map(|(_, menu)| menu));
+    profile::phase("status commands", start);
+    Ok(config)
+}
+
+/// Reads the config files at the provided path and the defaults, without deserializing them.
+/// The theme of the `theme` file, given with `--theme`, overrides theirs.
+///
+/// Files at the URLs listed in `include` are read after the defaults and before the config files,
+/// so that the config files override them. Including URLs requires the `remote_includes` option.
+///
+/// The variables in the `[vars]` table are substituted into the other values. See [vars].
+pub(crate) fn read_config(path: Option<PathBuf>, theme: Option<&Path>) -> Result<config::Config> {
+    let mut files = path
+        .as_deref()
+        .map(config_files)
+        .transpose()?
+        .unwrap_or_default();
+    let mut config = build_config(&[], None, &files)?;
+
+    // The imported colors come before the theme files, so that they can adjust them
+    let palette = config
+        .get_string("theme_colors")
+        .ok()
+        .filter(|file| !file.is_empty())
+        .map(|file| {
+            let file = theme_path(&file, path.as_deref());
+            palette::theme(&file)
+                .with_context(|| format!("Failed to import theme colors from '{}'", file.display()))
+        })
+        .transpose()?;
+
+    // The theme file of the config is read before the config files, so that their own themes
+    // override it, while the one passed with `--theme` overrides them
+    let theme_file = config
+        .get_string("theme_file")
+        .ok()
+        .filter(|file| !file.is_empty())
+        .map(|file| theme_path(&file, path.as_deref()));
+    let theme_flag = theme.map(util::expand_path);
+    let themed = palette.is_some() || theme_file.is_some() || theme_flag.is_some();
+    files.splice(0..0, theme_file);
+    files.extend(theme_flag);
+
+    let includes: Vec<String> = config.get("include").unwrap_or_default();
+    if includes.is_empty() {
+        if themed {
+            config = build_config(&[], palette.as_deref(), &files)?;
+        }
+        vars::substitute(&mut config)?;
+        return Ok(config);
+    }
+    if !config
+        .get_bool("options.remote_includes")
+        .unwrap_or_default()
+    {
+        bail!("The config includes remote URLs, but the remote_includes option isn't enabled");
+    }
+    let includes = includes
+        .iter()
+        .map(|url| remote::fetch(url).map(|file| (url, file)))
+        .collect::<Result<Vec<_>>>()?;
+    let mut config = build_config(&includes, palette.as_deref(), &files)?;
+    vars::substitute(&mut config)?;
+    Ok(config)
 }
 
-/// Loads the provided config file, and combines it with the defaults.
-pub(crate) fn load_config(file: PathBuf) -> Result<Config> {
-    let builder = config::Config::builder()
+/// Resolves the `theme_file` or `theme_colors` of a config. A relative path is relative to the
+/// config's directory.
+fn theme_path(file: &str, config: Option<&Path>) -> PathBuf {
+    let file = util::expand_path(Path::new(file));
+    let dir = match config {
+        Some(config) if config.is_dir() => config,
+        Some(config) => config.parent().unwrap_or_else(|| Path::new(".")),
+        None => return file,
+    };
+    dir.join(file)
+}
+
+/// Combines the defaults, the cached files of remote includes, the theme of imported colors (see
+/// [palette]), and config files, in order of increasing precedence.
+fn build_config(
+    includes: &[(&String, PathBuf)],
+    palette: Option<&str>,
+    files: &[PathBuf],
+) -> Result<config::Config> {
+    let mut builder = config::Config::builder()
         .add_source(config::File::from_str(
             DEFAULT_THEME,
             config::FileFormat::Toml,
@@ -55,11 +416,183 @@ pub(crate) fn load_config(file: PathBuf) -> Result<Config> {
             DEFAULT_KEYBINDS,
             config::FileFormat::Toml,
         ))
-        .add_source(config::File::from(file.clone()));
-    let config = builder
-        .build()
-        .context("Failed to read config sources")?
-        .try_deserialize::<Config>()
-        .context("Failed to deserialize config")?;
-    Ok(config)
+        .add_source(config::File::from_str(
+            DEFAULT_OPTIONS,
+            config::FileFormat::Toml,
+        ))
+        .add_source(config::File::from_str(
+            DEFAULT_LAYOUT,
+            config::FileFormat::Toml,
+        ))
+        .add_source(config::File::from_str(
+            DEFAULT_POPUP,
+            config::FileFormat::Toml,
+        ));
+    for (url, file) in includes {
+        // The format comes from the URL's path, ignoring any query
+        let url_path = url.split(['?', '#']).next().unwrap_or_default();
+        let format = config_format(Path::new(url_path))?;
+        builder = builder.add_source(config::File::from(file.as_path()).format(format));
+    }
+    if let Some(palette) = palette {
+        builder = builder.add_source(config::File::from_str(palette, config::FileFormat::Toml));
+    }
+    for file in files {
+        let format = config_format(file)?;
+        builder = builder.add_source(config::File::from(file.as_path()).format(format));
+    }
+    builder.build().context("Failed to read config sources")
+}
+
+/// Returns the locale that messages are shown in, from `$LC_ALL`, `$LC_MESSAGES`, or `$LANG`,
+/// without its encoding, e.g. `pt_BR` for `pt_BR.UTF-8`.
+fn locale() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|locale| !locale.is_empty())?;
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    match locale {
+        "" | "C" | "POSIX" => None,
+        locale => Some(locale.to_string()),
+    }
+}
+
+/// Applies the menu's translation for a locale, preferring one for the full locale, e.g. `pt_BR`,
+/// over one for just its language, e.g. `pt`.
+fn localize_menu(menu: &mut Menu, locale: &str) {
+    let language = locale.split('_').next().unwrap_or_default();
+    let find = |wanted: &str| {
+        menu.locales
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(wanted))
+            .map(|(_, translation)| translation)
+    };
+    let translation = match find(locale).or_else(|| find(language)) {
+        Some(translation) => translation,
+        None => return,
+    };
+    if let Some(prompt) = &translation.prompt {
+        menu.prompt = prompt.clone();
+    }
+    for (name, _) in menu.entries.iter_mut() {
+        if let Some(translated) = translation.entries.get(name) {
+            *name = translated.clone();
+        }
+    }
+    for name in menu.prefer.values_mut() {
+        if let Some(translated) = translation.entries.get(name) {
+            *name = translated.clone();
+        }
+    }
+}
+
+/// Gives the link entries of a menu that don't have an icon the `link_icon` option, unless it's
+/// empty.
+fn link_icons(menu: &mut Menu, icon: &str) {
+    if icon.is_empty() {
+        return;
+    }
+    for (_, entry) in menu.entries.iter_mut() {
+        if entry.kind == EntryKind::Link && entry.icon.is_none() {
+            entry.icon = Some(icon.to_string());
+        }
+    }
+}
+
+/// Expands `~` and environment variables in a menu's prompt, paths, and entry values. See
+/// [util::expand].
+fn expand_menu(menu: &mut Menu) {
+    menu.prompt = util::expand(&menu.prompt);
+    menu.path = menu.path.as_ref().map(|path| util::expand_path(path));
+    menu.terminal = menu
+        .terminal
+        .as_ref()
+        .map(|terminal| util::expand(terminal));
+    for (_, entry) in menu.entries.iter_mut() {
+        entry.value = util::expand(&entry.value);
+    }
+}
+
+/// Returns the provided config path, or else the first default config path that exists:
+/// `$XDG_CONFIG_HOME/fr33zmenu/config.toml`, then `/etc/fr33zmenu`.
+pub(crate) fn config_path(path: Option<PathBuf>) -> Result<PathBuf> {
+    if let Some(path) = path {
+        return Ok(util::expand_path(&path));
+    }
+    let user = util::xdg_dir("XDG_CONFIG_HOME", ".config")
+        .map(|dir| dir.join("fr33zmenu").join("config.toml"));
+    user.into_iter()
+        .chain([PathBuf::from(SYSTEM_CONFIG)])
+        .find(|path| path.exists())
+        .ok_or_else(|| {
+            anyhow!("No config path provided, and no config found in the default locations")
+        })
+}
+
+/// Resolves a config path into the files it refers to, in order of increasing precedence.
+///
+/// - A directory refers to every config file directly inside it.
+/// - A path with `*` or `?` in its file name refers to every matching file in its directory.
+/// - Any other path refers to itself.
+///
+/// Files are sorted by name, so the values in later files override those in earlier files.
+/// Tables, such as menus, are merged.
+pub(crate) fn config_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let (dir, pattern) = if path.is_dir() {
+        (path, None)
+    } else {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if !name.contains(['*', '?']) {
+            return Ok(vec![path.to_path_buf()]);
+        }
+        let dir = path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        (dir, Some(name))
+    };
+
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read config directory '{}'", dir.display()))?
+        .filter_map(|item| item.ok().map(|item| item.path()))
+        .filter(|file| file.is_file())
+        .filter(|file| match &pattern {
+            Some(pattern) => file
+                .file_name()
+                .is_some_and(|name| util::wildcard_match(pattern, &name.to_string_lossy())),
+            None => file
+                .extension()
+                .is_some_and(|ext| CONFIG_FORMATS.iter().any(|(e, _)| ext == *e)),
+        })
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        bail!("No config files found at '{}'", path.display());
+    }
+    Ok(files)
+}
+
+/// Detects the format of a config file from its extension. Files without an extension are
+/// assumed to be TOML.
+pub(crate) fn config_format(file: &Path) -> Result<config::FileFormat> {
+    let ext = match file.extension() {
+        Some(ext) => ext.to_string_lossy().to_lowercase(),
+        None => return Ok(config::FileFormat::Toml),
+    };
+    match CONFIG_FORMATS.iter().find(|(e, _)| *e == ext) {
+        Some((_, format)) => Ok(*format),
+        None => {
+            let supported: Vec<&str> = CONFIG_FORMATS.iter().map(|(e, _)| *e).collect();
+            bail!(
+                "Unsupported config format '.{ext}' of '{}', expected one of: {}",
+                file.display(),
+                supported.join(", ")
+            )
+        }
+    }
 }
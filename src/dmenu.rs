@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! dmenu mode, which shows entries read from stdin instead of the menus in the config.
+
+use std::io::{self, BufRead};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    args::Args,
+    config::{self, Config, Entry, Menu},
+    markup,
+};
+
+/// Loads the config used in dmenu mode: a single menu of the lines read from stdin, themed and
+/// bound according to the user's config if one exists. Each line is both an entry's name and its
+/// value, and the lines keep their order while the input is empty.
+///
+/// With `--markup`, the names are parsed as markup, but the values are the lines as they were
+/// read, so values aren't shown. Since the entries aren't in the config, the promote, disable, and
+/// reload keybinds are removed, and the history isn't used.
+pub(crate) fn config(args: &Args) -> Result<Config> {
+    let mut config = config::load_config(
+        config::config_path(args.config.clone()).ok(),
+        args.theme.as_deref(),
+    )?;
+    let mut menu = Menu {
+        prompt: args.prompt.clone(),
+        ..Default::default()
+    };
+    for (order, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.context("Failed to read entries from stdin")?;
+        if line.is_empty() {
+            continue;
+        }
+        let (name, markup) = if args.markup {
+            markup::parse(&line)
+        } else {
+            (line.clone(), Vec::new())
+        };
+        let entry = Entry {
+            order: order.try_into()?,
+            markup,
+            ..Entry::from(line)
+        };
+        menu.entries.push((name, entry));
+    }
+    config.menus = vec![("dmenu".to_string(), menu)];
+    config.keybinds.promote.clear();
+    config.keybinds.disable.clear();
+    config.keybinds.reload.clear();
+    config.options.show_values = false;
+    config.options.value_preview = false;
+    config.options.recent_menu = 0;
+    config.options.frecency = false;
+    config.options.remember_menu = false;
+    Ok(config)
+}
@@ -3,86 +3,439 @@
 
 use anyhow::Context;
 use crossterm::{
-    cursor::{MoveRight, MoveTo, MoveToColumn, MoveToNextLine, RestorePosition, SavePosition},
-    execute, queue,
-    style::{Print, ResetColor, SetAttributes, SetForegroundColor},
-    terminal::{self, Clear, ClearType},
+    cursor::{self, MoveRight, RestorePosition, SavePosition},
+    queue,
+    style::{Color, Print, ResetColor, SetAttributes, SetBackgroundColor, SetForegroundColor},
 };
 
 use crate::{
-    config::{Config, Menu},
+    args::Args,
+    config::{Config, EntryKind, Menu},
+    exec,
+    frame::Frame,
+    keybinds::Keybinds,
+    layout::{self, Columns, Layout, LayoutOptions},
+    markup,
+    options::{MatchMode, ValueAlign},
+    scrollback::{Outcome, Scrollback},
     set_style,
     state::State,
-    theme::Theme,
+    status::Status,
+    theme::{self, Theme, ThemeAttributes, ThemeColor},
+    util::{self, MatchedEntry},
 };
 
-// Spacing between elements on the same line
-const SPACING: u16 = 2;
+/// Width of the hotkey column, including spacing.
+pub(crate) const KEY_COLUMN_WIDTH: u16 = 2;
 
-const ROW_MENULINE: u16 = 0;
-const ROW_PROMPT: u16 = 2;
-const ROW_ENTRIES: u16 = 4;
+/// Width of the icon column, including spacing. Leaves room for double-width glyphs.
+pub(crate) const ICON_COLUMN_WIDTH: u16 = 3;
 
 /// Draws the interface.
 pub(crate) fn draw(
     tty: &mut impl std::io::Write,
+    args: &Args,
     config: &Config,
     state: &mut State,
     menu: &(String, Menu),
-    entries: &Vec<(Option<(i64, Vec<usize>)>, String, String)>,
+    entries: &[MatchedEntry],
+    scrollback: &Scrollback,
 ) -> Result<(), anyhow::Error> {
-    draw_menu_line(tty, &config.theme, &config.menus, state.menu_index)
-        .context("Failed to draw menu line")?;
-    draw_entries(
+    let (rows, frame) = (config.layout.rows(), state.frame);
+    draw_header_and_footer(tty, config, state, &menu.0)
+        .context("Failed to draw header and footer")?;
+    draw_menu_line(tty, config, state).context("Failed to draw menu line")?;
+    let mode = util::match_mode(state, &menu.1, &config.options);
+    draw_match_mode(
         tty,
+        &frame,
         &config.theme,
-        &entries,
-        state.entry_cursor,
-        state.entry_index,
+        rows.menu_line,
+        mode,
+        state.normal_mode,
     )
-    .context("Failed to draw entries")?;
-    draw_prompt(tty, &config.theme, &menu.1.prompt).context("Failed to draw prompt")?;
-    draw_input(tty, &config.theme, &state.input, state.cursor_x)
-        .context("Failed to draw user input")?;
+    .context("Failed to draw match mode")?;
+    let layout = layout::layout(state, &menu.1, config, entries)?;
+    if state.help {
+        draw_help(tty, config, state).context("Failed to draw help")?;
+    } else {
+        let quick_select = match layout.columns.quick_select {
+            true => config.keybinds.quick_select_keys(),
+            false => Vec::new(),
+        };
+        let theme = &config.theme;
+        draw_entries(
+            tty,
+            theme,
+            rows.entries,
+            entries,
+            &layout,
+            state,
+            &quick_select,
+        )
+        .context("Failed to draw entries")?;
+    }
+    draw_scrollback(tty, &frame, &config.theme, &config.layout, scrollback)
+        .context("Failed to draw scrollback")?;
+    if !state.help && (state.preview_width > 0 || state.preview_rows > 0) {
+        let lines = match util::preview_command(&menu.1, &config.options) {
+            Some(_) => state.preview_output.clone().unwrap_or_default(),
+            None => util::selected_entry(state, entries)
+                .filter(|_| state.entry_count > 0)
+                .map(|entry| entry_details(args, entry))
+                .unwrap_or_default(),
+        };
+        draw_preview_pane(tty, &config.theme, &config.layout, state, &lines)
+            .context("Failed to draw preview pane")?;
+    }
+    if state.describing_key {
+        draw_key_description(
+            tty,
+            &frame,
+            &config.theme,
+            rows.preview,
+            "Press a key to see what it does",
+        )
+        .context("Failed to draw key description")?;
+    } else if let Some(description) = &state.key_description {
+        draw_key_description(tty, &frame, &config.theme, rows.preview, description)
+            .context("Failed to draw key description")?;
+    } else if let Some(message) = &args.mesg {
+        draw_message(
+            tty,
+            &frame,
+            &config.theme,
+            rows.preview,
+            message,
+            args.markup,
+        )
+        .context("Failed to draw message")?;
+    } else if config.options.value_preview {
+        let preview = util::selected_entry(state, entries)
+            .filter(|entry| state.entry_count > 0 && !entry.2.info)
+            .map(|entry| exec::preview(args, &entry.2.value));
+        draw_value_preview(tty, &frame, &config.theme, rows.preview, preview.as_deref())
+            .context("Failed to draw value preview")?;
+    }
+    draw_prompt(tty, &frame, &config.theme, rows.prompt, &menu.1.prompt)
+        .context("Failed to draw prompt")?;
+    draw_input(tty, &config.theme, &state.input).context("Failed to draw user input")?;
+    let match_count = config
+        .options
+        .match_count
+        .then(|| util::match_count(state, entries));
+    if let Some(text) = &match_count {
+        let (theme, spacing) = (&config.theme, config.layout.spacing);
+        draw_match_count(
+            tty,
+            theme,
+            rows.prompt,
+            spacing,
+            &menu.1.prompt,
+            state,
+            text,
+        )
+        .context("Failed to draw match count")?;
+    }
+    draw_entry_position(
+        tty,
+        &config.theme,
+        rows.prompt,
+        config.layout.spacing,
+        &menu.1.prompt,
+        state,
+        match_count.as_deref(),
+    )
+    .context("Failed to draw entry position")?;
+    draw_border(tty, &frame, &config.theme).context("Failed to draw border")?;
+
+    // The cursor is placed last, after everything else on the prompt line is drawn
+    queue!(tty, RestorePosition)?;
+    let cursor_x = cursor_column(state);
+    if cursor_x > 0 {
+        let cursor_x: u16 = cursor_x.try_into()?;
+        queue!(tty, MoveRight(cursor_x))?;
+    }
+
+    Ok(())
+}
+
+/// Returns the terminal column of the input cursor, relative to the start of the input.
+fn cursor_column(state: &State) -> usize {
+    util::width(&state.input[..state.cursor()])
+}
 
+/// Draws the border of the box that the interface is drawn in, if it has one. It's drawn after
+/// the interface, so that anything that overflows the box doesn't cover it.
+fn draw_border(tty: &mut impl std::io::Write, frame: &Frame, theme: &Theme) -> anyhow::Result<()> {
+    let Some((area, [horizontal, vertical, top_left, top_right, bottom_left, bottom_right])) =
+        frame.border()
+    else {
+        return Ok(());
+    };
+    if area.width < 2 || area.height < 2 {
+        return Ok(());
+    }
+    let line = String::from(horizontal).repeat(usize::from(area.width - 2));
+    let bottom = area.y + area.height - 1;
+    queue!(
+        tty,
+        ResetColor,
+        set_style!(theme.box_border),
+        cursor::MoveTo(area.x, area.y),
+        Print(format!("{top_left}{line}{top_right}")),
+        cursor::MoveTo(area.x, bottom),
+        Print(format!("{bottom_left}{line}{bottom_right}"))
+    )?;
+    for y in area.y + 1..bottom {
+        queue!(
+            tty,
+            cursor::MoveTo(area.x, y),
+            Print(vertical),
+            cursor::MoveTo(area.x + area.width - 1, y),
+            Print(vertical)
+        )?;
+    }
+    queue!(tty, ResetColor)?;
+    Ok(())
+}
+
+/// Draws the header above the menu line and the footer in the bottom row, if they're set.
+fn draw_header_and_footer(
+    tty: &mut impl std::io::Write,
+    config: &Config,
+    state: &State,
+    menu: &str,
+) -> anyhow::Result<()> {
+    let (rows, frame) = (config.layout.rows(), &state.frame);
+    let (width, height) = frame.size();
+    let footer = (rows.footer > 0).then(|| height.saturating_sub(1));
+    let lines = [
+        (rows.header, &config.layout.header, &config.theme.header),
+        (footer, &config.layout.footer, &config.theme.footer),
+    ];
+    for (row, text, style) in lines {
+        let Some(row) = row else {
+            continue;
+        };
+        let text = text
+            .replace("{menu}", menu)
+            .replace("{count}", &state.entry_count.to_string());
+        queue!(
+            tty,
+            frame.clear_row(row),
+            ResetColor,
+            set_style!(style),
+            Print(util::truncate(&text, width.into())),
+            ResetColor
+        )?;
+    }
     Ok(())
 }
 
 fn draw_menu_line(
     tty: &mut impl std::io::Write,
-    theme: &Theme,
-    menus: &Vec<(String, Menu)>,
-    menu_index: usize,
+    config: &Config,
+    state: &State,
 ) -> anyhow::Result<()> {
-    let mut x: u16 = 0;
-    for (i, menu) in menus.iter().enumerate() {
-        let style = if i == menu_index {
+    let Some(row) = config.layout.rows().menu_line else {
+        return Ok(());
+    };
+    let frame = &state.frame;
+    let (theme, menus, options) = (&config.theme, &config.menus, &config.layout.tabs);
+    let width = frame.size().0;
+    let tabs = layout::tabs(menus, &config.layout, state)?;
+    for (i, (menu, tab)) in menus.iter().zip(&tabs.ranges).enumerate() {
+        if tab.is_empty() {
+            continue; // Scrolled out of view
+        }
+        let active = i == state.tab_index();
+        let style = if active {
             &theme.menu_cursor
         } else {
             &theme.menu_name
         };
 
-        execute!(
+        let label = options.label(&menu.0, active);
+        let label = util::truncate(&label, width.saturating_sub(tab.start).into());
+        queue!(
             tty,
             ResetColor,
-            MoveTo(x, ROW_MENULINE),
+            frame.move_to(tab.start, row),
             set_style!(style),
-            Print(&menu.0)
+            Print(label)
+        )?;
+
+        // The separator is only drawn between tabs that are both in view
+        let next_in_view = tabs.ranges.get(i + 1).is_some_and(|next| !next.is_empty());
+        if !options.separator.is_empty() && next_in_view {
+            queue!(
+                tty,
+                ResetColor,
+                frame.move_to(tab.end, row),
+                set_style!(theme.menu_separator),
+                Print(&options.separator)
+            )?;
+        }
+    }
+    if tabs.hidden_left {
+        queue!(
+            tty,
+            ResetColor,
+            frame.move_to(0, row),
+            set_style!(theme.menu_overflow),
+            Print(layout::TABS_HIDDEN_LEFT)
+        )?;
+    }
+    if tabs.hidden_right {
+        let marker_width: u16 = util::width(layout::TABS_HIDDEN_RIGHT).try_into()?;
+        queue!(
+            tty,
+            ResetColor,
+            frame.move_to(width.saturating_sub(marker_width), row),
+            set_style!(theme.menu_overflow),
+            Print(layout::TABS_HIDDEN_RIGHT)
+        )?;
+    }
+    for (crumb, range) in &tabs.breadcrumbs {
+        queue!(
+            tty,
+            ResetColor,
+            frame.move_to(range.start, row),
+            set_style!(theme.menu_breadcrumb),
+            Print(crumb)
+        )?;
+    }
+    queue!(tty, ResetColor)?;
+    Ok(())
+}
+
+/// Draws the match mode at the end of the menu line, unless it's fuzzy, and whether the
+/// `vim_mode` normal mode is active.
+fn draw_match_mode(
+    tty: &mut impl std::io::Write,
+    frame: &Frame,
+    theme: &Theme,
+    row: Option<u16>,
+    mode: MatchMode,
+    normal_mode: bool,
+) -> anyhow::Result<()> {
+    let mut modes = Vec::new();
+    if normal_mode {
+        modes.push("[normal]".to_string());
+    }
+    if mode != MatchMode::Fuzzy {
+        modes.push(format!("[{}]", mode.name()));
+    }
+    let row = match row {
+        Some(row) if !modes.is_empty() => row,
+        _ => return Ok(()),
+    };
+    let text = modes.join(" ");
+    let width: u16 = frame.size().0;
+    let len: u16 = util::width(&text).try_into()?;
+    queue!(
+        tty,
+        ResetColor,
+        frame.move_to(width.saturating_sub(len), row),
+        set_style!(theme.match_mode),
+        Print(text),
+        ResetColor
+    )?;
+    Ok(())
+}
+
+/// Draws the value that will be submitted on the line under the prompt. Values too long for the
+/// line are cut off with an overflow indicator.
+fn draw_value_preview(
+    tty: &mut impl std::io::Write,
+    frame: &Frame,
+    theme: &Theme,
+    row: u16,
+    preview: Option<&str>,
+) -> anyhow::Result<()> {
+    queue!(tty, frame.clear_row(row), ResetColor)?;
+    let preview = match preview {
+        Some(preview) => preview,
+        None => return Ok(()),
+    };
+    let width: usize = frame.size().0.into();
+    if util::width(preview) <= width {
+        queue!(tty, set_style!(theme.value_preview), Print(preview))?;
+    } else {
+        let preview = util::truncate(preview, width.saturating_sub(1));
+        queue!(
+            tty,
+            set_style!(theme.value_preview),
+            Print(preview),
+            set_style!(theme.overflow),
+            Print("+")
         )?;
-        x += &menu.0.len().try_into()? + SPACING;
     }
+    queue!(tty, ResetColor)?;
+    Ok(())
+}
+
+/// Draws what a key press does on the line under the prompt.
+fn draw_key_description(
+    tty: &mut impl std::io::Write,
+    frame: &Frame,
+    theme: &Theme,
+    row: u16,
+    description: &str,
+) -> anyhow::Result<()> {
+    let width: usize = frame.size().0.into();
+    let description = util::truncate(description, width);
+    queue!(
+        tty,
+        frame.clear_row(row),
+        ResetColor,
+        set_style!(theme.key_description),
+        Print(description),
+        ResetColor
+    )?;
+    Ok(())
+}
+
+/// Draws the message given with `--mesg` on the line under the prompt.
+fn draw_message(
+    tty: &mut impl std::io::Write,
+    frame: &Frame,
+    theme: &Theme,
+    row: u16,
+    message: &str,
+    markup: bool,
+) -> anyhow::Result<()> {
+    let (message, spans) = if markup {
+        markup::parse(message)
+    } else {
+        (message.to_string(), Vec::new())
+    };
+    let width: usize = frame.size().0.into();
+    let message = util::truncate(&message, width);
+    queue!(tty, frame.clear_row(row), ResetColor)?;
+    for (i, c) in message.chars().enumerate() {
+        queue!(tty, ResetColor, set_style!(theme.message))?;
+        if let Some(span) = markup::at(&spans, i) {
+            set_markup(tty, span, false)?;
+        }
+        queue!(tty, Print(c))?;
+    }
+    queue!(tty, ResetColor)?;
     Ok(())
 }
 
 fn draw_prompt(
     tty: &mut impl std::io::Write,
+    frame: &Frame,
     theme: &Theme,
+    row: u16,
     text: &str,
 ) -> Result<(), std::io::Error> {
-    execute!(
+    queue!(
         tty,
-        MoveTo(0, ROW_PROMPT),
         ResetColor,
+        frame.clear_row(row),
         set_style!(theme.prompt),
         Print(text),
         ResetColor,
@@ -94,71 +447,355 @@ fn draw_input(
     tty: &mut impl std::io::Write,
     theme: &Theme,
     text: &str,
-    cursor_x: u16,
-) -> Result<(), anyhow::Error> {
-    execute!(
+) -> Result<(), std::io::Error> {
+    queue!(
         tty,
         RestorePosition,
-        Clear(ClearType::UntilNewLine),
         set_style!(theme.input),
         Print(text),
-        RestorePosition
+        ResetColor
+    )
+}
+
+/// Draws the number of matching entries out of all the entries, like `[5/87]`, after the input.
+/// Nothing is drawn if it doesn't fit.
+fn draw_match_count(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    row: u16,
+    spacing: u16,
+    prompt: &str,
+    state: &State,
+    text: &str,
+) -> anyhow::Result<()> {
+    let frame = &state.frame;
+    let width: u16 = frame.size().0;
+    let x: u16 = (util::width(prompt) + util::width(&state.input)).try_into()?;
+    let x = x.saturating_add(spacing);
+    let len: u16 = util::width(text).try_into()?;
+    if x.saturating_add(len) > width {
+        return Ok(());
+    }
+    queue!(
+        tty,
+        frame.move_to(x, row),
+        set_style!(theme.match_count),
+        Print(text),
+        ResetColor
     )?;
-    if cursor_x > 0 {
-        execute!(tty, MoveRight(cursor_x))?;
+    Ok(())
+}
+
+/// Draws the position of the selected entry among the selectable entries, like `12/87`, at the
+/// end of the prompt line. Nothing is drawn if it would overlap the input, or the match count.
+fn draw_entry_position(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    row: u16,
+    spacing: u16,
+    prompt: &str,
+    state: &State,
+    match_count: Option<&str>,
+) -> anyhow::Result<()> {
+    if state.entry_count == 0 {
+        return Ok(());
     }
+    let text = format!("{}/{}", state.entry_index + 1, state.entry_count);
+    let frame = &state.frame;
+    let width: u16 = frame.size().0;
+    let len: u16 = util::width(&text).try_into()?;
+    let match_count = match_count.map_or(0, |text| usize::from(spacing) + util::width(text));
+    let input_end: u16 =
+        (util::width(prompt) + util::width(&state.input) + match_count).try_into()?;
+    if input_end.saturating_add(spacing + len) > width {
+        return Ok(());
+    }
+    queue!(
+        tty,
+        frame.move_to(width - len, row),
+        set_style!(theme.entry_position),
+        Print(text),
+        ResetColor
+    )?;
     Ok(())
 }
 
 fn draw_entries(
     tty: &mut impl std::io::Write,
     theme: &Theme,
-    entries: &Vec<(Option<(i64, Vec<usize>)>, String, String)>,
-    entry_cursor: bool,
-    entry_index: usize,
+    top: u16,
+    entries: &[MatchedEntry],
+    layout: &Layout,
+    state: &State,
+    quick_select: &[char],
 ) -> anyhow::Result<()> {
-    queue!(tty, MoveTo(0, ROW_ENTRIES), ResetColor)?;
+    queue!(tty, ResetColor)?;
 
-    let size = terminal::size()?;
-    let w = size.0;
-    let h: usize = size.1.try_into()?;
+    let (frame, w) = (&state.frame, util::entries_width(state));
+    let rows = state.entry_rows;
+    if rows == 0 {
+        return Ok(()); // No room to draw anything
+    }
+    let visible = state.entry_scroll..state.entry_scroll + rows;
+    let top = usize::from(top);
 
-    for (i, entry) in entries.iter().enumerate() {
-        let y = i + 4; // TODO what's the proper value here? where does it come from?
+    // Entries on the rows above the visible ones are skipped, but still counted towards the entry
+    // index
+    let mut selectable_index = 0;
+    let mut quick_select = quick_select.iter();
+    let mut below = 0;
+    for (entry, &(row, column)) in entries.iter().zip(&layout.cells) {
+        let selectable = util::is_selectable(state, entry);
+        let selected = selectable && state.entry_cursor && selectable_index == state.entry_index;
+        if selectable {
+            selectable_index += 1;
+        }
+        if !visible.contains(&row) {
+            if row >= visible.end {
+                below += 1;
+            }
+            continue;
+        }
 
-        if y < h {
-            let selected = entry_cursor && i == entry_index;
-            draw_entry(tty, theme, w, entry, selected)?;
-        } else if i == 0 {
-            break; // No room to draw anything
-        } else {
-            let msg = format!("+{} more", entries.len() - i + 1);
-            queue!(
-                tty,
-                set_style!(theme.overflow),
-                MoveToNextLine(1),
-                MoveToNextLine(1),
-                Clear(ClearType::CurrentLine),
-                Print(msg)
-            )?;
-            break;
+        let y: u16 = (top + row - state.entry_scroll).try_into()?;
+        match entry.2.kind {
+            EntryKind::Normal | EntryKind::Submenu | EntryKind::Link => {
+                let key = if !layout.columns.quick_select {
+                    entry.2.key
+                } else if selectable {
+                    quick_select.next().copied()
+                } else {
+                    None
+                };
+                let x: u16 = (usize::from(layout.cell_width) * column).try_into()?;
+                queue!(tty, frame.move_to(x, y))?;
+                if layout.per_row > 1 {
+                    draw_entry_cell(tty, theme, frame.column(x), layout, entry, key, selected)?
+                } else {
+                    draw_entry(tty, theme, frame.column(x), layout, entry, key, selected)?
+                }
+            }
+            EntryKind::Separator => {
+                queue!(tty, frame.move_to(0, y))?;
+                draw_separator(tty, theme, w)?
+            }
+            EntryKind::Label => {
+                queue!(tty, frame.move_to(0, y))?;
+                draw_label(tty, theme, &entry.1)?
+            }
         }
     }
 
+    // The row after the visible entries is reserved for the overflow indicator
+    if below > 0 {
+        let y: u16 = (top + rows).try_into()?;
+        queue!(
+            tty,
+            frame.clear_row(y),
+            set_style!(theme.overflow),
+            Print(format!("+{below} more"))
+        )?;
+    }
+
     Ok(())
 }
 
+/// Draws the key, icon, and name of an entry that shares its row with other entries, starting at
+/// terminal column `x`. Names too long for the entry's width are cut off.
+fn draw_entry_cell(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    x: u16,
+    layout: &Layout,
+    entry: &MatchedEntry,
+    key: Option<char>,
+    selected: bool,
+) -> Result<(), anyhow::Error> {
+    let columns = &layout.columns;
+    draw_entry_key_and_icon(tty, theme, x, entry, key, columns, None)?;
+    queue!(tty, cursor::MoveToColumn(x + columns.name))?;
+    let max_len = layout
+        .cell_width
+        .saturating_sub(columns.name + columns.spacing);
+    draw_entry_name(tty, theme, entry, selected, max_len.into())
+}
+
+/// Draws an entry that takes a whole row, starting at terminal column `x`.
 fn draw_entry(
     tty: &mut impl std::io::Write,
     theme: &Theme,
+    x: u16,
+    layout: &Layout,
+    entry: &MatchedEntry,
+    key: Option<char>,
+    selected: bool,
+) -> Result<(), anyhow::Error> {
+    let (columns, term_width) = (&layout.columns, layout.cell_width);
+    // The cursor's background is filled in first, and kept behind the other columns
+    let row_bg = match selected && columns.full_line_cursor {
+        true => theme::color(&theme.entry_cursor.bg),
+        false => None,
+    };
+    if row_bg.is_some() {
+        queue!(
+            tty,
+            ResetColor,
+            cursor::MoveToColumn(x),
+            set_style!(theme.entry_cursor),
+            Print(" ".repeat(term_width.into()))
+        )?;
+    }
+    draw_entry_key_and_icon(tty, theme, x, entry, key, columns, row_bg)?;
+    // Icons may be wider than they seem, so the name is always placed explicitly
+    queue!(tty, cursor::MoveToColumn(x + columns.name))?;
+    let max_len = term_width.saturating_sub(columns.name + columns.spacing);
+    draw_entry_name(tty, theme, entry, selected, max_len.into())?;
 
-    term_width: u16,
-    entry: &(Option<(i64, Vec<usize>)>, String, String),
+    let name_width: u16 = util::width(util::truncate(&entry.1, max_len.into())).try_into()?;
+    let mut name_width = columns.name + name_width + columns.spacing;
+
+    // Draw description after the name
+    if let Some(desc) = &entry.2.desc {
+        let style = match entry.0 {
+            Some(_) => &theme.entry_desc,
+            None => &theme.entry_hidden,
+        };
+        name_width = name_width.max(columns.desc);
+        // Descriptions make way for left-aligned values
+        let desc_end = match columns.value_align {
+            ValueAlign::Left if columns.value => columns.value_x.saturating_sub(columns.spacing),
+            _ => term_width,
+        };
+        let remaining_cols = desc_end.saturating_sub(name_width);
+        let desc_trunc = util::truncate(desc, remaining_cols.into());
+        let desc_width: u16 = util::width(desc_trunc).try_into()?;
+        queue!(tty, ResetColor, set_style!(style))?;
+        set_row_background(tty, row_bg)?;
+        queue!(tty, cursor::MoveToColumn(x + name_width), Print(desc_trunc))?;
+        name_width += desc_width + columns.spacing;
+    }
+
+    // Draw value on right side, or in a column after the names and descriptions
+    if !columns.value {
+        return Ok(());
+    }
+    let value = &entry.2.value;
+    let value_width: u16 = util::width(value).try_into()?;
+    let value_x = match columns.value_align {
+        ValueAlign::Right => name_width,
+        ValueAlign::Left => name_width.max(columns.value_x),
+    };
+    let mut remaining_cols = term_width.saturating_sub(value_x);
+    if columns.value_max_width > 0 {
+        remaining_cols = remaining_cols.min(columns.value_max_width.try_into().unwrap_or(u16::MAX));
+    }
+    let column = |width: u16| match columns.value_align {
+        ValueAlign::Right => term_width - width,
+        ValueAlign::Left => value_x,
+    };
+
+    let style = match (&entry.0, entry.2.status) {
+        (Some(_), _) if entry.2.info => &theme.entry_info,
+        (Some(_), Some(Status::Ok)) => &theme.entry_value_ok,
+        (Some(_), Some(Status::Warn)) => &theme.entry_value_warn,
+        (Some(_), Some(Status::Err)) => &theme.entry_value_err,
+        (Some(_), None) => &theme.entry_value,
+        (None, _) => &theme.entry_hidden,
+    };
+
+    if remaining_cols >= value_width {
+        queue!(tty, ResetColor, set_style!(style))?;
+        set_row_background(tty, row_bg)?;
+        queue!(
+            tty,
+            cursor::MoveToColumn(x + column(value_width)),
+            Print(value)
+        )?;
+    } else if remaining_cols >= 4 {
+        // at least 1 char + ellipses
+        let overflow_indicator = "+";
+        let overflow_indicator_width: u16 = util::width(overflow_indicator).try_into()?;
+        let vt = util::truncate(value, (remaining_cols - overflow_indicator_width).into());
+        let value_trunc_width: u16 = util::width(vt).try_into()?;
+        let value_total_width: u16 = value_trunc_width + overflow_indicator_width;
+        queue!(tty, ResetColor, set_style!(style))?;
+        set_row_background(tty, row_bg)?;
+        queue!(
+            tty,
+            cursor::MoveToColumn(x + column(value_total_width)),
+            Print(vt),
+            set_style!(theme.overflow)
+        )?;
+        set_row_background(tty, row_bg)?;
+        queue!(tty, Print(overflow_indicator))?;
+    }
+    Ok(())
+}
+
+/// Replaces the background of the current style with the selected entry's, when its whole row is
+/// highlighted.
+fn set_row_background(
+    tty: &mut impl std::io::Write,
+    row_bg: Option<Color>,
+) -> Result<(), std::io::Error> {
+    if let Some(bg) = row_bg {
+        queue!(tty, SetBackgroundColor(bg))?;
+    }
+    Ok(())
+}
+
+/// Draws the key column, with the entry's hotkey or quick select key, and the icon column of an
+/// entry that starts at terminal column `x`. Their background is replaced with `row_bg` if it's
+/// provided.
+fn draw_entry_key_and_icon(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    x: u16,
+    entry: &MatchedEntry,
+    key: Option<char>,
+    columns: &Columns,
+    row_bg: Option<Color>,
+) -> Result<(), std::io::Error> {
+    if columns.key {
+        let key = key.unwrap_or(' ');
+        queue!(
+            tty,
+            ResetColor,
+            cursor::MoveToColumn(x),
+            set_style!(theme.entry_key)
+        )?;
+        set_row_background(tty, row_bg)?;
+        queue!(tty, Print(key), ResetColor)?;
+    }
+    if columns.icon {
+        let icon_x = x + if columns.key { KEY_COLUMN_WIDTH } else { 0 };
+        let icon = entry.2.icon.as_deref().unwrap_or_default();
+        queue!(
+            tty,
+            ResetColor,
+            cursor::MoveToColumn(icon_x),
+            set_style!(theme.entry_icon)
+        )?;
+        set_row_background(tty, row_bg)?;
+        queue!(tty, Print(icon), ResetColor)?;
+    }
+    Ok(())
+}
+
+/// Draws the name of an entry, highlighting the letters that match the input. Names wider than
+/// `max_width` columns are cut off.
+fn draw_entry_name(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    entry: &MatchedEntry,
     selected: bool,
+    max_width: usize,
 ) -> Result<(), anyhow::Error> {
+    let name = util::truncate(&entry.1, max_width);
     if let Some(fuzzy) = &entry.0 {
-        for (j, c) in entry.1.char_indices() {
-            let style = if fuzzy.1.contains(&j) {
+        // Matches are indexed by character, not by byte, so multibyte names highlight correctly
+        for (k, c) in name.chars().enumerate() {
+            let style = if fuzzy.1.contains(&k) {
                 if selected {
                     &theme.entry_cursor_match
                 } else {
@@ -167,63 +804,333 @@ fn draw_entry(
             } else {
                 if selected {
                     &theme.entry_cursor
+                } else if entry.2.info {
+                    &theme.entry_info
                 } else {
                     &theme.entry_name
                 }
             };
-            queue!(tty, ResetColor, set_style!(style), Print(c))?;
+            queue!(tty, ResetColor, set_style!(style))?;
+            if let Some(span) = markup::at(&entry.2.markup, k) {
+                set_markup(tty, span, selected)?;
+            }
+            queue!(tty, Print(c))?;
         }
     } else {
         queue!(
             tty,
             ResetColor,
-            SetForegroundColor(theme.entry_hidden.fg.0),
-            SetAttributes(theme.entry_hidden.attrs.0),
-            Print(&entry.1)
+            SetForegroundColor(theme::color(&theme.entry_hidden.fg).unwrap_or(Color::Reset)),
+            SetAttributes(theme::attributes(&theme.entry_hidden.attrs)),
+            Print(name)
         )?;
     }
+    Ok(())
+}
 
-    // Draw value on right side
-    let name_width: u16 = entry.1.len().try_into()?;
-    let name_width = name_width + SPACING;
-    let value_width: u16 = entry.2.len().try_into()?;
-    let remaining_cols = term_width.saturating_sub(name_width);
+/// Adds the formatting of a markup span to the current style. The color is left alone on the
+/// cursor, so that the selected entry stays readable, and when colors aren't drawn.
+fn set_markup(
+    tty: &mut impl std::io::Write,
+    span: &markup::Span,
+    selected: bool,
+) -> std::io::Result<()> {
+    let attrs = theme::attributes(&ThemeAttributes(span.attrs));
+    queue!(tty, SetAttributes(attrs))?;
+    let fg = span.fg.filter(|_| !selected);
+    if let Some(fg) = fg.and_then(|fg| theme::color(&ThemeColor(fg))) {
+        queue!(tty, SetForegroundColor(fg))?;
+    }
+    Ok(())
+}
 
-    let style = match entry.0 {
-        Some(_) => &theme.entry_value,
-        None => &theme.entry_hidden,
-    };
+fn draw_scrollback(
+    tty: &mut impl std::io::Write,
+    frame: &Frame,
+    theme: &Theme,
+    layout: &LayoutOptions,
+    scrollback: &Scrollback,
+) -> anyhow::Result<()> {
+    let h = frame.size().1.saturating_sub(layout.rows().footer);
+    let executions: Vec<_> = scrollback.recent().collect();
+    let top = h.saturating_sub(executions.len().try_into()?);
 
-    if remaining_cols >= value_width {
+    for (y, execution) in (top..).zip(executions) {
+        let (style, text) = match &execution.outcome {
+            Outcome::Running => (&theme.scrollback_running, format!("… {}", execution.name)),
+            Outcome::Success => (&theme.scrollback_success, format!("✓ {}", execution.name)),
+            Outcome::Failure(reason) => (
+                &theme.scrollback_failure,
+                format!("✗ {} ({reason})", execution.name),
+            ),
+        };
         queue!(
             tty,
+            frame.clear_row(y),
             ResetColor,
             set_style!(style),
-            MoveToColumn(term_width - value_width),
-            Print(&entry.2)
+            Print(text),
+            ResetColor
         )?;
-    } else if remaining_cols >= 4 {
-        // at least 1 char + ellipses
-        let overflow_indicator = "+";
-        let overflow_indicator_width: u16 = overflow_indicator.len().try_into()?;
-        let value_trunc = entry
-            .2
-            .get(..(remaining_cols - overflow_indicator_width).into());
-
-        if let Some(vt) = value_trunc {
-            let value_trunc_width: u16 = vt.len().try_into()?;
-            let value_total_width: u16 = value_trunc_width + overflow_indicator_width;
+    }
+    Ok(())
+}
+
+/// Draws every bound command with its keybinds and description in place of the entries.
+/// Commands that don't fit are counted with the overflow indicator.
+fn draw_help(tty: &mut impl std::io::Write, config: &Config, state: &State) -> anyhow::Result<()> {
+    let (theme, frame) = (&config.theme, &state.frame);
+    let width: usize = frame.size().0.into();
+    let rows: Vec<(&str, String)> = config
+        .keybinds
+        .actions()
+        .into_iter()
+        .filter(|(_, keybinds)| !keybinds.is_empty())
+        .map(|(action, keybinds)| {
+            let keys: Vec<String> = keybinds.iter().map(|kb| kb.to_string()).collect();
+            (action, keys.join(", "))
+        })
+        .collect();
+    let action_width = rows.iter().map(|row| row.0.len()).max().unwrap_or_default();
+    let keys_width = rows
+        .iter()
+        .map(|row| util::width(&row.1))
+        .max()
+        .unwrap_or_default();
+
+    // The row reserved for the overflow indicator is only used if every command fits
+    let fits = rows.len() <= state.entry_rows + 1;
+    let shown = if fits { rows.len() } else { state.entry_rows };
+    let spacing = usize::from(config.layout.spacing);
+    let keys_x = action_width + spacing;
+    let desc_x = keys_x + keys_width + spacing;
+    let (keys_column, desc_column): (u16, u16) = (keys_x.try_into()?, desc_x.try_into()?);
+    let top = config.layout.rows().entries;
+    for (y, (action, keys)) in (top..).zip(&rows[..shown]) {
+        let keys = if keys.is_empty() { "-" } else { keys };
+        let desc = util::truncate(Keybinds::description(action), width.saturating_sub(desc_x));
+        queue!(
+            tty,
+            frame.clear_row(y),
+            set_style!(theme.entry_name),
+            Print(util::truncate(action, width)),
+            frame.move_to_column(keys_column),
+            set_style!(theme.entry_key),
+            Print(util::truncate(keys, width.saturating_sub(keys_x))),
+            frame.move_to_column(desc_column),
+            set_style!(theme.entry_desc),
+            Print(desc),
+            ResetColor
+        )?;
+    }
+    if !fits {
+        let y: u16 = (usize::from(top) + shown).try_into()?;
+        queue!(
+            tty,
+            frame.clear_row(y),
+            set_style!(theme.overflow),
+            Print(format!("+{} more", rows.len() - shown)),
+            ResetColor
+        )?;
+    }
+    Ok(())
+}
+
+/// Returns the details of an entry shown in the preview pane when there's no preview command:
+/// the command that submitting it runs, and its description.
+fn entry_details(args: &Args, entry: &MatchedEntry) -> Vec<String> {
+    let mut lines = vec![exec::preview(args, &entry.2.value)];
+    if let Some(desc) = &entry.2.desc {
+        lines.push(String::new());
+        lines.push(desc.clone());
+    }
+    lines
+}
+
+/// Draws the preview pane to the right of or below the entries, with a border between them.
+/// Lines that are too long are wrapped.
+fn draw_preview_pane(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    layout: &LayoutOptions,
+    state: &State,
+    lines: &[String],
+) -> anyhow::Result<()> {
+    let frame = &state.frame;
+    let (width, height) = frame.size();
+    let bottom = height
+        .saturating_sub(layout.rows().footer)
+        .saturating_sub(state.scrollback_rows.try_into()?);
+    let (spacing, entries_row) = (layout.spacing, layout.rows().entries);
+    let (x, top, text_width) = if state.preview_width > 0 {
+        let x = width.saturating_sub(state.preview_width);
+        for y in entries_row..bottom {
             queue!(
                 tty,
+                frame.move_to(x, y),
                 ResetColor,
-                set_style!(style),
-                MoveToColumn(term_width - value_total_width),
-                Print(vt),
-                set_style!(theme.overflow),
-                Print(overflow_indicator)
+                set_style!(theme.preview_border),
+                Print("│")
             )?;
         }
+        (
+            x + spacing,
+            entries_row,
+            state.preview_width.saturating_sub(spacing),
+        )
+    } else {
+        let y = bottom.saturating_sub(state.preview_rows.try_into()?);
+        queue!(
+            tty,
+            frame.move_to(0, y),
+            ResetColor,
+            set_style!(theme.preview_border),
+            Print("─".repeat(width.into()))
+        )?;
+        (0, y + 1, width)
+    };
+    if text_width == 0 {
+        return Ok(());
     }
-    queue!(tty, MoveToNextLine(1), MoveToColumn(0))?;
+
+    let rows = lines
+        .iter()
+        .flat_map(|line| wrap(line, text_width.into()))
+        .take(bottom.saturating_sub(top).into());
+    for (y, row) in (top..).zip(rows) {
+        queue!(
+            tty,
+            frame.move_to(x, y),
+            ResetColor,
+            set_style!(theme.preview),
+            Print(row)
+        )?;
+    }
+    queue!(tty, ResetColor)?;
     Ok(())
 }
+
+/// Splits text into rows that are at most `width` wide. Empty text is a single empty row.
+fn wrap(text: &str, width: usize) -> Vec<&str> {
+    let mut rows = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let row = util::truncate(rest, width);
+        if row.is_empty() {
+            break; // A character wider than the row
+        }
+        rows.push(row);
+        rest = &rest[row.len()..];
+    }
+    if rows.is_empty() {
+        rows.push("");
+    }
+    rows
+}
+
+fn draw_separator(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    term_width: u16,
+) -> Result<(), std::io::Error> {
+    queue!(
+        tty,
+        ResetColor,
+        set_style!(theme.entry_separator),
+        Print("─".repeat(term_width.into())),
+        ResetColor
+    )
+}
+
+fn draw_label(
+    tty: &mut impl std::io::Write,
+    theme: &Theme,
+    text: &str,
+) -> Result<(), std::io::Error> {
+    queue!(
+        tty,
+        ResetColor,
+        set_style!(theme.entry_label),
+        Print(text),
+        ResetColor
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_column_counts_display_width() {
+        let mut state = State::default();
+        for c in ["日", "本", "é", "🦀", "x"] {
+            state.insert_input(c);
+        }
+        assert_eq!(cursor_column(&state), 8);
+        state.set_cursor(state.char_back());
+        assert_eq!(cursor_column(&state), 7);
+        state.set_cursor(state.char_back());
+        assert_eq!(cursor_column(&state), 5);
+        state.set_cursor(0);
+        assert_eq!(cursor_column(&state), 0);
+    }
+
+    #[test]
+    fn cursor_column_counts_sequences_once() {
+        let mut state = State::default();
+        state.insert_input("e\u{301}");
+        assert_eq!(cursor_column(&state), 1);
+        state.insert_input("👩\u{200d}🔬");
+        assert_eq!(cursor_column(&state), 3);
+        state.insert_input("x");
+        assert_eq!(cursor_column(&state), 4);
+    }
+
+    #[test]
+    fn wrap_keeps_wide_characters_and_sequences_whole() {
+        assert_eq!(wrap("日本語", 4), ["日本", "語"]);
+        assert_eq!(wrap("日本語", 3), ["日", "本", "語"]);
+        assert_eq!(
+            wrap("e\u{301}e\u{301}e\u{301}", 2),
+            ["e\u{301}e\u{301}", "e\u{301}"]
+        );
+        assert_eq!(
+            wrap("👩\u{200d}🔬👩\u{200d}🔬", 3),
+            ["👩\u{200d}🔬", "👩\u{200d}🔬"]
+        );
+        // A character wider than the row can't be wrapped
+        assert_eq!(wrap("日", 1), [""]);
+    }
+
+    #[test]
+    fn draw_entry_truncates_the_name_to_the_row() {
+        let theme = crate::config::load_config(None, None).unwrap().theme;
+        let layout = Layout {
+            columns: Columns {
+                key: false,
+                quick_select: false,
+                icon: false,
+                name: 0,
+                desc: 0,
+                spacing: 1,
+                value: false,
+                value_align: ValueAlign::Right,
+                value_x: 0,
+                value_max_width: 0,
+                full_line_cursor: false,
+            },
+            per_row: 1,
+            cell_width: 8,
+            cells: Vec::new(),
+        };
+        let entry = (
+            Some((0, Vec::new())),
+            "abcdefghij".to_string(),
+            Default::default(),
+        );
+        let mut out = Vec::new();
+        draw_entry(&mut out, &theme, 0, &layout, &entry, None, false).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains('g') && !out.contains('h'), "{out:?}");
+    }
+}
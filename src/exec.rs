@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Executes selections.
+
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+
+use crate::args::Args;
+
+/// Returns the command line that submitting a selection runs, or the selection itself if it's
+/// output instead of executed.
+pub(crate) fn preview(args: &Args, selection: &str) -> String {
+    match &args.exec_with {
+        Some(e) => format!("{e} {selection}"),
+        None => selection.to_string(),
+    }
+}
+
+/// Builds the command that executes a selection according to `--exec` or `--exec-with`, with
+/// its standard streams detached. Returns `None` if neither was provided.
+pub(crate) fn command(args: &Args, selection: &str) -> Result<Option<Command>> {
+    if args.exec {
+        return Ok(Some(execute(selection)));
+    }
+    match &args.exec_with {
+        Some(exec_with) => execute_with(exec_with, selection).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Builds the command that executes a selection with another command, as with `--exec-with` or
+/// the `exec_with` of a custom action, with its standard streams detached.
+pub(crate) fn execute_with(exec_with: &str, selection: &str) -> Result<Command> {
+    let mut split = exec_with.split(' ');
+    let cmd = split.next().ok_or_else(|| anyhow!("empty exec_with"))?;
+    let mut command = Command::new(cmd);
+    command.args(split).arg(selection);
+    detach(&mut command);
+    Ok(command)
+}
+
+/// Builds the command that executes a selection as with `--exec`, with its standard streams
+/// detached.
+pub(crate) fn execute(selection: &str) -> Command {
+    let mut command = Command::new("nohup");
+    command.arg(selection);
+    detach(&mut command);
+    command
+}
+
+/// Builds the command that opens the URL of a link entry with the `opener` option, with its
+/// standard streams detached. Links are opened this way regardless of `--exec` or `--exec-with`.
+pub(crate) fn open_link(opener: &str, url: &str) -> Result<Command> {
+    let mut split = opener.split(' ');
+    let cmd = split.next().filter(|cmd| !cmd.is_empty());
+    let mut command = Command::new(cmd.ok_or_else(|| anyhow!("empty opener"))?);
+    command.args(split).arg(url);
+    detach(&mut command);
+    Ok(command)
+}
+
+/// Detaches a command's standard streams from the terminal.
+fn detach(command: &mut Command) {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+}
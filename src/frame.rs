@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Places the interface in a frame: the whole terminal, or a centered box with an optional border
+//! and padding, as set by the `box_*` options.
+//!
+//! The interface is drawn with the commands made by [Frame], which take positions within the
+//! frame, instead of crossterm's, which take positions within the terminal.
+
+use std::{fmt, io};
+
+use crossterm::{cursor, terminal, Command};
+
+use crate::options::Options;
+
+/// A rectangle of the terminal.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Area {
+    pub(crate) x: u16,
+    pub(crate) y: u16,
+    pub(crate) width: u16,
+    pub(crate) height: u16,
+}
+
+/// The areas that the frame settings result in for the current terminal size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Frame {
+    /// The box, including its border.
+    outer: Area,
+
+    /// The area that the interface is drawn in.
+    inner: Area,
+
+    /// The characters that the box is bordered with, if it has a border.
+    border: Option<[char; 6]>,
+}
+
+impl Frame {
+    /// Fits the frame to the terminal's current size. Called before each frame is drawn.
+    pub(crate) fn new(options: &Options) -> io::Result<Frame> {
+        let (width, height) = terminal::size()?;
+        let fit = |size: u16, max: u16| if size == 0 { max } else { size.min(max) };
+        let outer = Area {
+            width: fit(options.box_width, width),
+            height: fit(options.box_height, height),
+            x: (width - fit(options.box_width, width)) / 2,
+            y: (height - fit(options.box_height, height)) / 2,
+        };
+        let border = options.box_border.0;
+        let inset = options.box_padding + u16::from(border.is_some());
+        Ok(Frame {
+            outer,
+            inner: Area {
+                x: outer.x + inset.min(outer.width / 2),
+                y: outer.y + inset.min(outer.height / 2),
+                width: outer.width.saturating_sub(inset * 2),
+                height: outer.height.saturating_sub(inset * 2),
+            },
+            border,
+        })
+    }
+
+    /// Returns the width and height of the area that the interface is drawn in.
+    pub(crate) fn size(&self) -> (u16, u16) {
+        (self.inner.width, self.inner.height)
+    }
+
+    /// Returns the box and its border characters, if it has a border.
+    pub(crate) fn border(&self) -> Option<(Area, [char; 6])> {
+        self.border.map(|chars| (self.outer, chars))
+    }
+
+    /// Converts a position in the terminal, e.g. of a mouse click, to a position in the frame.
+    /// Returns `None` if it's outside of the frame.
+    pub(crate) fn relative(&self, column: u16, row: u16) -> Option<(u16, u16)> {
+        let inner = self.inner;
+        let x = column.checked_sub(inner.x).filter(|x| *x < inner.width)?;
+        let y = row.checked_sub(inner.y).filter(|y| *y < inner.height)?;
+        Some((x, y))
+    }
+
+    /// Converts a column of the frame to a column of the terminal.
+    pub(crate) fn column(&self, x: u16) -> u16 {
+        self.inner.x + x
+    }
+
+    /// Moves the cursor to a column and row of the frame.
+    pub(crate) fn move_to(&self, x: u16, y: u16) -> cursor::MoveTo {
+        cursor::MoveTo(self.inner.x + x, self.inner.y + y)
+    }
+
+    /// Moves the cursor to a column of the frame, in the current row.
+    pub(crate) fn move_to_column(&self, x: u16) -> cursor::MoveToColumn {
+        cursor::MoveToColumn(self.inner.x + x)
+    }
+
+    /// Clears a row of the frame, without touching the rest of the terminal's row, and moves the
+    /// cursor to its start.
+    pub(crate) fn clear_row(&self, y: u16) -> ClearRow {
+        ClearRow {
+            area: self.inner,
+            row: y,
+        }
+    }
+}
+
+/// Clears a row of the frame. Made with [Frame::clear_row].
+pub(crate) struct ClearRow {
+    area: Area,
+    row: u16,
+}
+
+impl Command for ClearRow {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        let start = cursor::MoveTo(self.area.x, self.area.y + self.row);
+        start.write_ansi(f)?;
+        write!(f, "{:1$}", "", usize::from(self.area.width))?;
+        start.write_ansi(f)
+    }
+}
+
+#[cfg(test)]
+impl Frame {
+    /// Returns the frame of a terminal of the given size, without a box.
+    pub(crate) fn sized(width: u16, height: u16) -> Frame {
+        let area = Area {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        };
+        Frame {
+            outer: area,
+            inner: area,
+            border: None,
+        }
+    }
+}
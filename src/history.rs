@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! A record of submitted entries.
+//!
+//! The history is stored in `$XDG_STATE_HOME/fr33zmenu/history`, with one submission per line
+//! written as tab-separated fields: `time menu entry`, where `time` is a Unix timestamp.
+//!
+//! The name of the last active menu is stored alongside it, in `last_menu`.
+//!
+//! Several instances may run at once, so the history is locked while it's read, appended to, or
+//! rewritten, and `last_menu` is replaced atomically. The history is rewritten by replacing it
+//! atomically too, so a writer that locks it checks that it's still the file at its path.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::util;
+
+/// Set by `--demo`, in which case nothing is written to the history or `last_menu`.
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Makes [record] and [save_last_menu] do nothing.
+pub(crate) fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::Relaxed);
+}
+
+/// A submission read from the history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Record {
+    /// When the entry was submitted, in seconds since the Unix epoch.
+    pub(crate) time: u64,
+
+    /// The name of the menu holding the entry.
+    pub(crate) menu: String,
+
+    /// The name of the entry.
+    pub(crate) entry: String,
+}
+
+/// Returns the path of the history file.
+fn history_path() -> Option<PathBuf> {
+    util::xdg_dir("XDG_STATE_HOME", ".local/state").map(|dir| dir.join("fr33zmenu/history"))
+}
+
+/// Returns the path of the file holding the name of the last active menu.
+fn last_menu_path() -> Option<PathBuf> {
+    util::xdg_dir("XDG_STATE_HOME", ".local/state").map(|dir| dir.join("fr33zmenu/last_menu"))
+}
+
+/// Returns the name of the last active menu, if one was saved.
+pub(crate) fn last_menu() -> Option<String> {
+    let name = fs::read_to_string(last_menu_path()?).ok()?;
+    Some(name.trim_end_matches('\n').to_string())
+}
+
+/// Saves the name of the last active menu.
+pub(crate) fn save_last_menu(menu: &str) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let path = last_menu_path().ok_or_else(|| io::Error::other("$HOME is unset"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    util::write_atomic(&path, format!("{menu}\n"))
+}
+
+/// Appends a submission to the history.
+pub(crate) fn record(menu: &str, entry: &str) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let path = history_path().ok_or_else(|| io::Error::other("$HOME is unset"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    append(&path, menu, entry)
+}
+
+/// Appends a submission to the history at `path`.
+fn append(path: &Path, menu: &str, entry: &str) -> io::Result<()> {
+    let line = format!(
+        "{}\t{}\t{}\n",
+        util::unix_time(),
+        sanitize(menu),
+        sanitize(entry)
+    );
+    // Several instances may record at once, so the line is written whole while holding the lock
+    let mut file = lock(path, OpenOptions::new().create(true).append(true))?;
+    file.write_all(line.as_bytes())
+}
+
+/// Opens the history and locks it exclusively. If it was replaced while waiting for the lock, the
+/// file that replaced it is opened and locked instead, since writing to the replaced file would
+/// be lost.
+fn lock(path: &Path, options: &OpenOptions) -> io::Result<File> {
+    loop {
+        let file = options.open(path)?;
+        file.lock()?;
+        match fs::metadata(path) {
+            Ok(metadata) if metadata.ino() == file.metadata()?.ino() => return Ok(file),
+            Ok(_) => continue,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Returns a menu or entry name as it's written in the history. Tabs and newlines would break the
+/// format, so they're replaced with spaces.
+fn sanitize(name: &str) -> String {
+    name.replace(['\t', '\n'], " ")
+}
+
+/// Removes the most recent submission of an entry from the history, or every submission of it if
+/// `all` is set. A missing history is left missing.
+pub(crate) fn forget(menu: &str, entry: &str, all: bool) -> io::Result<()> {
+    if READ_ONLY.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    let path = history_path().ok_or_else(|| io::Error::other("$HOME is unset"))?;
+    remove(&path, menu, entry, all)
+}
+
+/// Removes submissions of an entry from the history at `path`, as in [forget].
+fn remove(path: &Path, menu: &str, entry: &str, all: bool) -> io::Result<()> {
+    let mut file = match lock(path, OpenOptions::new().read(true)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+
+    let (menu, entry) = (sanitize(menu), sanitize(entry));
+    let is_match = |line: &&str| {
+        let mut fields = line.splitn(3, '\t').skip(1);
+        fields.next() == Some(menu.as_str()) && fields.next() == Some(entry.as_str())
+    };
+    let mut lines: Vec<&str> = text.lines().collect();
+    if all {
+        lines.retain(|line| !is_match(line));
+    } else if let Some(index) = lines.iter().rposition(is_match) {
+        lines.remove(index);
+    }
+    let text: String = lines.iter().map(|line| format!("{line}\n")).collect();
+
+    // The history is replaced while the replaced file is still locked, so that a crash while
+    // writing can't lose it, and writers waiting for the lock move on to the new file
+    util::write_atomic(path, text)
+}
+
+/// Reads every submission in the history, oldest first. Malformed lines are skipped, and a
+/// missing history is empty.
+pub(crate) fn read() -> Vec<Record> {
+    let text = match history_path().map(|path| read_locked(&path)) {
+        Some(Ok(text)) => text,
+        _ => return Vec::new(),
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            Some(Record {
+                time: fields.next()?.parse().ok()?,
+                menu: fields.next()?.to_string(),
+                entry: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The number of days after which a submission counts for half as much towards frecency.
+const FRECENCY_HALF_LIFE_DAYS: f64 = 7.0;
+
+/// Reads a file while holding a shared lock on it, so that it isn't read while a line is being
+/// appended.
+fn read_locked(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    file.lock_shared()?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Returns the frecency of each submitted entry, keyed by `(menu, entry)`. Each submission adds
+/// 1000 when it's new, decaying by half every [FRECENCY_HALF_LIFE_DAYS], so entries that are
+/// submitted both often and recently score highest.
+pub(crate) fn frecency() -> HashMap<(String, String), u64> {
+    let now = util::unix_time();
+    let mut scores: HashMap<(String, String), f64> = HashMap::new();
+    for record in read() {
+        let age_days = now.saturating_sub(record.time) as f64 / 86400.0;
+        let score = 1000.0 * 0.5f64.powf(age_days / FRECENCY_HALF_LIFE_DAYS);
+        *scores.entry((record.menu, record.entry)).or_default() += score;
+    }
+    scores
+        .into_iter()
+        .map(|(key, score)| (key, score.round() as u64))
+        .collect()
+}
+
+/// Returns the number of submissions from each menu.
+pub(crate) fn menu_counts() -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for record in read() {
+        *counts.entry(record.menu).or_default() += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process, thread};
+
+    use super::*;
+
+    /// Returns the path of an empty history in a temporary directory unique to the test.
+    fn history(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("fr33zmenu-{test}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("history")
+    }
+
+    #[test]
+    fn concurrent_appends_are_not_lost_or_interleaved() {
+        const WRITERS: usize = 8;
+        const LINES: usize = 200;
+
+        let path = history("appends");
+
+        // Each writer opens the file separately, as separate instances would
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let path = path.clone();
+                thread::spawn(move || {
+                    let entry = format!("entry {writer} {}", "x".repeat(500));
+                    for _ in 0..LINES {
+                        append(&path, "menu", &entry).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), WRITERS * LINES);
+        for writer in 0..WRITERS {
+            let entry = format!("entry {writer} {}", "x".repeat(500));
+            let count = lines
+                .iter()
+                .filter(|line| line.splitn(3, '\t').nth(2) == Some(entry.as_str()))
+                .count();
+            assert_eq!(
+                count, LINES,
+                "lines of writer {writer} were lost or interleaved"
+            );
+        }
+    }
+
+    #[test]
+    fn remove_keeps_concurrent_appends() {
+        const LINES: usize = 300;
+
+        let path = history("remove");
+        for _ in 0..LINES {
+            append(&path, "menu", "forgotten").unwrap();
+        }
+
+        // Appends that wait for the lock while the history is replaced go to the new file
+        let writer = {
+            let path = path.clone();
+            thread::spawn(move || {
+                for _ in 0..LINES {
+                    append(&path, "menu", "kept").unwrap();
+                }
+            })
+        };
+        for _ in 0..LINES {
+            remove(&path, "menu", "forgotten", false).unwrap();
+        }
+        writer.join().unwrap();
+
+        let text = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        let entries: Vec<&str> = text
+            .lines()
+            .filter_map(|line| line.splitn(3, '\t').nth(2))
+            .collect();
+        assert_eq!(entries, vec!["kept"; LINES]);
+    }
+
+    #[test]
+    fn remove_most_recent_or_all() {
+        let path = history("remove-all");
+        for entry in ["a", "b", "a", "a"] {
+            append(&path, "menu", entry).unwrap();
+        }
+        remove(&path, "menu", "a", false).unwrap();
+        let count = |entry: &str| {
+            fs::read_to_string(&path)
+                .unwrap()
+                .lines()
+                .filter(|line| line.ends_with(&format!("\t{entry}")))
+                .count()
+        };
+        assert_eq!((count("a"), count("b")), (2, 1));
+        remove(&path, "menu", "a", true).unwrap();
+        assert_eq!((count("a"), count("b")), (0, 1));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Generates a starter config.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{config, util};
+
+static EXAMPLE_MENUS: &str = include_str!("../config/menu.example.toml");
+
+/// Writes a starter config to `path`, or `$XDG_CONFIG_HOME/fr33zmenu/config.toml` if no path is
+/// provided. The config holds example menus, and the default options, keybinds, and theme.
+/// Existing files are only overwritten if `force` is set. Returns the path written to.
+pub(crate) fn init(path: Option<PathBuf>, force: bool) -> Result<PathBuf> {
+    let path = match path {
+        Some(path) => util::expand_path(&path),
+        None => util::xdg_dir("XDG_CONFIG_HOME", ".config")
+            .map(|dir| dir.join("fr33zmenu").join("config.toml"))
+            .ok_or_else(|| anyhow!("Can't find the config directory, because $HOME is unset"))?,
+    };
+    if path.exists() && !force {
+        bail!(
+            "'{}' already exists, use --force to overwrite it",
+            path.display()
+        );
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create config directory '{}'", dir.display()))?;
+    }
+
+    let contents = [
+        "# fr33zmenu config. See https://github.com/fr33zing/fr33zmenu#configuration\n",
+        EXAMPLE_MENUS,
+        "# Defaults. Remove anything you don't want to change.\n",
+        config::DEFAULT_OPTIONS,
+        config::DEFAULT_LAYOUT,
+        config::DEFAULT_POPUP,
+        config::DEFAULT_KEYBINDS,
+        config::DEFAULT_THEME,
+    ]
+    .join("\n");
+    fs::write(&path, contents)
+        .with_context(|| format!("Failed to write config file '{}'", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,104 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Keeps a single instance of the interface open, for `--single-instance`.
+//!
+//! The running instance holds a lock on `$XDG_RUNTIME_DIR/fr33zmenu.lock`, and listens on the
+//! socket `$XDG_RUNTIME_DIR/fr33zmenu.sock`. Another instance that fails to take the lock asks it
+//! to raise its window through the socket, and exits.
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+/// The message that asks the running instance to raise its window.
+const RAISE: &[u8] = b"raise\n";
+
+/// How long to wait for a message from another instance that has connected.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The lock and socket of the running instance. The socket is removed when it's dropped.
+pub(crate) struct Instance {
+    /// Held until the program exits.
+    _lock: File,
+    listener: UnixListener,
+    socket: PathBuf,
+}
+
+impl Instance {
+    /// Indicates that another instance has asked this one to raise its window since the last
+    /// call. Doesn't block.
+    pub(crate) fn raise_requested(&self) -> bool {
+        let mut requested = false;
+        while let Ok((mut stream, _)) = self.listener.accept() {
+            let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+            let mut message = [0; RAISE.len()];
+            if stream.read_exact(&mut message).is_ok() && message == RAISE {
+                requested = true;
+            }
+        }
+        requested
+    }
+}
+
+impl Drop for Instance {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+    }
+}
+
+/// Returns the directory that the lock and socket are kept in.
+fn runtime_dir() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => env::temp_dir(),
+    }
+}
+
+/// Claims the single instance. Returns `None` if another instance is already open, after asking
+/// it to raise its window.
+pub(crate) fn claim() -> Result<Option<Instance>> {
+    let dir = runtime_dir();
+    let lock_path = dir.join("fr33zmenu.lock");
+    let socket = dir.join("fr33zmenu.sock");
+
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open lock file {}", lock_path.display()))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            // The running instance may not be listening yet, in which case it's already being
+            // opened, so there's nothing to raise
+            if let Ok(mut stream) = UnixStream::connect(&socket) {
+                let _ = stream.write_all(RAISE);
+            }
+            return Ok(None);
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(e)
+                .with_context(|| format!("Failed to lock lock file {}", lock_path.display()));
+        }
+    }
+
+    // A socket left behind by an instance that crashed would prevent binding
+    let _ = fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Failed to listen on socket {}", socket.display()))?;
+    listener
+        .set_nonblocking(true)
+        .context("Failed to make the instance socket nonblocking")?;
+    Ok(Some(Instance {
+        _lock: lock,
+        listener,
+        socket,
+    }))
+}
@@ -11,14 +11,18 @@ use serde::{
 };
 
 use crate::{
-    handle_key_event,
+    handle_key_event, menu_jumps,
     state::{Action, State},
 };
 
 /// Indicates that unhandled key events should cause errors.
 const UNHANDLED_KEY_EVENT_ERRORS: bool = false;
 
-#[derive(Debug)]
+/// The keys that are pressed with alt to submit the visible entries, in order, with the
+/// `quick_select` option.
+const QUICK_SELECT_KEYS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 /// Used to deserialize keybinds from a plus-seperated list of modifier keys and one non-modifier
 /// key.
 pub(crate) struct Keybind(
@@ -29,13 +33,57 @@ pub(crate) struct Keybind(
 );
 
 impl Keybind {
-    fn matches(&self, event: KeyEvent) -> bool {
-        let code = if event.code == KeyCode::BackTab {
-            KeyCode::Tab
-        } else {
-            event.code
-        };
-        code == self.0 && event.modifiers == self.1
+    /// Returns the keybind that a key event is matched as. Terminals report shift+tab as a
+    /// separate key, and shifted letters as uppercase, so these are converted to the lowercase key
+    /// with the shift modifier, since keybinds are case-insensitive.
+    pub(crate) fn from_event(event: KeyEvent) -> Self {
+        match event.code {
+            KeyCode::BackTab => Keybind(KeyCode::Tab, event.modifiers | KeyModifiers::SHIFT),
+            KeyCode::Char(c) if c.is_uppercase() => Keybind(
+                KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                event.modifiers | KeyModifiers::SHIFT,
+            ),
+            code => Keybind(code, event.modifiers),
+        }
+    }
+
+    pub(crate) fn matches(&self, event: KeyEvent) -> bool {
+        Keybind::from_event(event) == *self
+    }
+}
+
+impl fmt::Display for Keybind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+        ] {
+            if self.1.contains(modifier) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match self.0 {
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Tab | KeyCode::BackTab => write!(f, "tab"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Esc => write!(f, "escape"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char('+') => write!(f, "plus"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            code => write!(f, "{code:?}"),
+        }
     }
 }
 
@@ -83,6 +131,8 @@ impl<'de> Deserialize<'de> for Keybind {
                         "delete" | "del" => c = Some(KeyCode::Delete),
                         "insert" => c = Some(KeyCode::Insert),
                         "escape" | "esc" => c = Some(KeyCode::Esc),
+                        "space" => c = Some(KeyCode::Char(' ')),
+                        "plus" => c = Some(KeyCode::Char('+')),
                         _ => {
                             let mut chars = key.chars();
                             if let Some(first_char) = chars.next() {
@@ -141,26 +191,231 @@ pub(crate) struct Keybinds {
     /// Delete the previous character at the input cursor.
     pub(crate) delete_back: Vec<Keybind>,
 
+    /// Delete the word before the input cursor.
+    pub(crate) delete_word_back: Vec<Keybind>,
+
+    /// Delete the input after the input cursor.
+    pub(crate) delete_to_end: Vec<Keybind>,
+
+    /// Delete the input before the input cursor.
+    pub(crate) delete_to_start: Vec<Keybind>,
+
     /// Move the input cursor to the right.
     pub(crate) input_next: Vec<Keybind>,
 
     /// Move the input cursor to the left.
     pub(crate) input_back: Vec<Keybind>,
 
+    /// Move the input cursor to the end of the next word.
+    pub(crate) input_word_next: Vec<Keybind>,
+
+    /// Move the input cursor to the start of the previous word.
+    pub(crate) input_word_back: Vec<Keybind>,
+
+    /// Move the input cursor to the start of the input.
+    pub(crate) input_start: Vec<Keybind>,
+
+    /// Move the input cursor to the end of the input.
+    pub(crate) input_end: Vec<Keybind>,
+
     /// Go to the next menu to the right.
     pub(crate) menu_next: Vec<Keybind>,
 
     /// Go to the previous menu to the left.
     pub(crate) menu_back: Vec<Keybind>,
 
+    /// Return to the menu that the current submenu was opened from.
+    pub(crate) menu_parent: Vec<Keybind>,
+
     /// Select the next entry.
     pub(crate) entry_next: Vec<Keybind>,
 
     /// Select the previous entry.
     pub(crate) entry_back: Vec<Keybind>,
+
+    /// Move the selection down by a page of entries.
+    pub(crate) entry_page_next: Vec<Keybind>,
+
+    /// Move the selection up by a page of entries.
+    pub(crate) entry_page_back: Vec<Keybind>,
+
+    /// Add the selected entry, or the input if nothing matches it, to a menu in the config file.
+    pub(crate) promote: Vec<Keybind>,
+
+    /// Switch to the next match mode: fuzzy, substring, prefix, then exact.
+    pub(crate) match_mode: Vec<Keybind>,
+
+    /// Disable the selected entry in the config file, which hides it without deleting it.
+    pub(crate) disable: Vec<Keybind>,
+
+    /// Show or hide the values on the right side of the entries.
+    pub(crate) toggle_values: Vec<Keybind>,
+
+    /// Describe what the next key press does, without doing it.
+    pub(crate) describe_key: Vec<Keybind>,
+
+    /// Show a list of all keybinds over the entries.
+    pub(crate) help: Vec<Keybind>,
+
+    /// Insert the system clipboard into the input at the cursor.
+    pub(crate) paste: Vec<Keybind>,
+
+    /// Copy the value of the selected entry to the system clipboard, without exiting.
+    pub(crate) yank: Vec<Keybind>,
+
+    /// Remove the selected entry of the recent menu from the history.
+    pub(crate) forget: Vec<Keybind>,
+
+    /// Replace the input with the name of the selected entry, or the best match if none is
+    /// selected.
+    pub(crate) complete: Vec<Keybind>,
+
+    /// Read the config files again, keeping the input and, if it still exists, the selection.
+    pub(crate) reload: Vec<Keybind>,
+
+    /// Go to the first menu tab. `menu_2` to `menu_9` go to the tabs after it. Unbound by
+    /// default, since `alt` and a digit quick selects an entry.
+    #[serde(default)]
+    pub(crate) menu_1: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_2: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_3: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_4: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_5: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_6: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_7: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_8: Vec<Keybind>,
+    #[serde(default)]
+    pub(crate) menu_9: Vec<Keybind>,
 }
 
 impl Keybinds {
+    /// Returns the name of each command, paired with its keybinds.
+    pub(crate) fn actions(&self) -> Vec<(&'static str, &[Keybind])> {
+        vec![
+            ("exit", &self.exit),
+            ("submit", &self.submit),
+            ("clear", &self.clear),
+            ("delete_next", &self.delete_next),
+            ("delete_back", &self.delete_back),
+            ("delete_word_back", &self.delete_word_back),
+            ("delete_to_end", &self.delete_to_end),
+            ("delete_to_start", &self.delete_to_start),
+            ("input_next", &self.input_next),
+            ("input_back", &self.input_back),
+            ("input_word_next", &self.input_word_next),
+            ("input_word_back", &self.input_word_back),
+            ("input_start", &self.input_start),
+            ("input_end", &self.input_end),
+            ("menu_next", &self.menu_next),
+            ("menu_back", &self.menu_back),
+            ("menu_parent", &self.menu_parent),
+            ("entry_next", &self.entry_next),
+            ("entry_back", &self.entry_back),
+            ("entry_page_next", &self.entry_page_next),
+            ("entry_page_back", &self.entry_page_back),
+            ("promote", &self.promote),
+            ("match_mode", &self.match_mode),
+            ("disable", &self.disable),
+            ("toggle_values", &self.toggle_values),
+            ("describe_key", &self.describe_key),
+            ("help", &self.help),
+            ("paste", &self.paste),
+            ("yank", &self.yank),
+            ("forget", &self.forget),
+            ("complete", &self.complete),
+            ("reload", &self.reload),
+            ("menu_1", &self.menu_1),
+            ("menu_2", &self.menu_2),
+            ("menu_3", &self.menu_3),
+            ("menu_4", &self.menu_4),
+            ("menu_5", &self.menu_5),
+            ("menu_6", &self.menu_6),
+            ("menu_7", &self.menu_7),
+            ("menu_8", &self.menu_8),
+            ("menu_9", &self.menu_9),
+        ]
+    }
+
+    /// Returns a short description of what a command does.
+    pub(crate) fn description(action: &str) -> &'static str {
+        match action {
+            "exit" => "Quit the program",
+            "submit" => "Submit the selected entry",
+            "clear" => "Clear the input",
+            "delete_next" => "Delete the character after the input cursor",
+            "delete_back" => "Delete the character before the input cursor",
+            "delete_word_back" => "Delete the word before the input cursor",
+            "delete_to_end" => "Delete the input after the input cursor",
+            "delete_to_start" => "Delete the input before the input cursor",
+            "input_next" => "Move the input cursor to the right",
+            "input_back" => "Move the input cursor to the left",
+            "input_word_next" => "Move the input cursor to the end of the next word",
+            "input_word_back" => "Move the input cursor to the start of the previous word",
+            "input_start" => "Move the input cursor to the start of the input",
+            "input_end" => "Move the input cursor to the end of the input",
+            "menu_next" => "Go to the next menu",
+            "menu_back" => "Go to the previous menu",
+            "menu_parent" => "Return to the menu that the submenu was opened from",
+            "entry_next" => "Select the next entry",
+            "entry_back" => "Select the previous entry",
+            "entry_page_next" => "Move the selection down by a page",
+            "entry_page_back" => "Move the selection up by a page",
+            "promote" => "Add the selected entry or the input to a menu in the config",
+            "match_mode" => "Switch to the next match mode",
+            "disable" => "Disable the selected entry in the config",
+            "toggle_values" => "Show or hide entry values",
+            "describe_key" => "Describe what the next key press does",
+            "help" => "Show this list of keybinds",
+            "paste" => "Insert the clipboard into the input",
+            "yank" => "Copy the value of the selected entry to the clipboard",
+            "forget" => "Remove the selected recent entry from the history",
+            "complete" => "Replace the input with the name of the selected entry",
+            "reload" => "Read the config again",
+            "menu_1" | "menu_2" | "menu_3" | "menu_4" | "menu_5" | "menu_6" | "menu_7"
+            | "menu_8" | "menu_9" => "Go to the menu tab at this position",
+            _ => "",
+        }
+    }
+
+    /// Returns the quick select keys, leaving out those that are bound with alt to a command.
+    pub(crate) fn quick_select_keys(&self) -> Vec<char> {
+        let actions = self.actions();
+        QUICK_SELECT_KEYS
+            .chars()
+            .filter(|&c| {
+                let keybind = Keybind(KeyCode::Char(c), KeyModifiers::ALT);
+                !actions
+                    .iter()
+                    .any(|(_, keybinds)| keybinds.contains(&keybind))
+            })
+            .collect()
+    }
+
+    /// Describes what pressing a key does, without doing it.
+    pub(crate) fn describe(&self, event: KeyEvent) -> String {
+        let key = Keybind::from_event(event);
+        let action = self
+            .actions()
+            .into_iter()
+            .find(|(_, keybinds)| keybinds.iter().any(|kb| kb.matches(event)));
+        match action {
+            Some((name, _)) => format!("{key}: {name} - {}", Keybinds::description(name)),
+            None if matches!(event.code, KeyCode::Char(_))
+                && (event.modifiers - KeyModifiers::SHIFT).is_empty() =>
+            {
+                format!("{key}: types into the input")
+            }
+            None => format!("{key} isn't bound"),
+        }
+    }
+
     pub(crate) fn handle(&self, event: KeyEvent, state: State) -> Result<State> {
         let (handled, state_res) = handle_key_event!(
             self,
@@ -172,12 +427,42 @@ impl Keybinds {
                 clear,
                 delete_next,
                 delete_back,
+                delete_word_back,
+                delete_to_end,
+                delete_to_start,
                 input_next,
                 input_back,
+                input_word_next,
+                input_word_back,
+                input_start,
+                input_end,
                 entry_next,
                 entry_back,
+                entry_page_next,
+                entry_page_back,
                 menu_next,
-                menu_back
+                menu_back,
+                menu_parent,
+                promote,
+                match_mode,
+                disable,
+                toggle_values,
+                describe_key,
+                help,
+                paste,
+                yank,
+                forget,
+                complete,
+                reload,
+                menu_1,
+                menu_2,
+                menu_3,
+                menu_4,
+                menu_5,
+                menu_6,
+                menu_7,
+                menu_8,
+                menu_9
             ]
         );
         let state = state_res.context("Keybind handler error")?;
@@ -188,19 +473,76 @@ impl Keybinds {
         }
     }
 
+    /// Handles a key press with the `vim_mode` option. Escape enters normal mode from insert
+    /// mode, and in normal mode, letters navigate instead of typing. Returns `false` along with
+    /// the unchanged state if the key press should be handled by the keybinds instead.
+    pub(crate) fn handle_vim(event: KeyEvent, state: State) -> Result<(bool, State)> {
+        if !state.normal_mode {
+            if event.code != KeyCode::Esc || !event.modifiers.is_empty() {
+                return Ok((false, state));
+            }
+            let state = State {
+                normal_mode: true,
+                entry_cursor: state.entry_count > 0,
+                ..state
+            };
+            return Ok((true, state));
+        }
+
+        let key = match event.code {
+            KeyCode::Char(c) if (event.modifiers - KeyModifiers::SHIFT).is_empty() => c,
+            _ => return Ok((false, state)),
+        };
+        let pending = state.pending_key;
+        let state = State {
+            pending_key: None,
+            ..state
+        };
+        let state = match (pending, key) {
+            (_, 'j') => Keybinds::entry_next(state)?,
+            (_, 'k') => Keybinds::entry_back(state)?,
+            (_, 'h') => Keybinds::menu_back(state)?,
+            (_, 'l') => Keybinds::menu_next(state)?,
+            (Some('g'), 'g') if state.entry_count > 0 => State {
+                entry_cursor: true,
+                entry_index: 0,
+                ..state
+            },
+            (_, 'g') => State {
+                pending_key: Some('g'),
+                ..state
+            },
+            (_, 'G') if state.entry_count > 0 => State {
+                entry_cursor: true,
+                entry_index: state.entry_count - 1,
+                ..state
+            },
+            (_, 'i' | 'a' | 'I' | 'A') => {
+                let mut state = match key {
+                    'a' => Keybinds::input_next(state)?,
+                    'I' => Keybinds::input_start(state)?,
+                    'A' => Keybinds::input_end(state)?,
+                    _ => state,
+                };
+                let cursor = state.cursor();
+                state.set_cursor(cursor);
+                State {
+                    normal_mode: false,
+                    ..state
+                }
+            }
+            // Other letters don't type in normal mode
+            _ => state,
+        };
+        Ok((true, state))
+    }
+
     fn fallback_handler(state: State, event: KeyEvent) -> Result<State> {
         let new_state = match event.code {
             KeyCode::Char(c) => {
                 if event.modifiers.bits() <= 1 {
-                    let mut input = state.input.clone();
-                    input.insert(state.cursor_x.into(), c);
-                    let state = State {
-                        input,
-                        cursor_x: state.cursor_x.saturating_add(1),
-                        entry_cursor: false,
-                        entry_index: 0,
-                        ..state.clone()
-                    };
+                    let mut state = state.clone();
+                    state.insert_input(c.encode_utf8(&mut [0; 4]));
                     Some(state)
                 } else {
                     None
@@ -234,71 +576,175 @@ impl Keybinds {
         Ok(state)
     }
 
-    fn clear(state: State) -> Result<State> {
+    fn promote(state: State) -> Result<State> {
         let state = State {
-            input: String::default(),
-            cursor_x: 0,
-            entry_cursor: false,
+            action: Action::Promote,
             ..state
         };
         Ok(state)
     }
 
-    fn delete_next(state: State) -> Result<State> {
+    fn disable(state: State) -> Result<State> {
         let state = State {
+            action: Action::Disable,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn describe_key(state: State) -> Result<State> {
+        let state = State {
+            describing_key: true,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn help(state: State) -> Result<State> {
+        let state = State {
+            help: true,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn toggle_values(state: State) -> Result<State> {
+        let state = State {
+            action: Action::ToggleValues,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn paste(state: State) -> Result<State> {
+        let state = State {
+            action: Action::Paste,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn yank(state: State) -> Result<State> {
+        let state = State {
+            action: Action::Yank,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn forget(state: State) -> Result<State> {
+        let state = State {
+            action: Action::Forget,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn complete(state: State) -> Result<State> {
+        let state = State {
+            action: Action::Complete,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn reload(state: State) -> Result<State> {
+        let state = State {
+            action: Action::Reload,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn match_mode(state: State) -> Result<State> {
+        let state = State {
+            action: Action::MatchMode,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn clear(state: State) -> Result<State> {
+        let state = State {
+            input: String::default(),
+            cursor_x: 0,
             entry_cursor: false,
-            input: state
-                .input
-                .char_indices()
-                .filter_map(|(i, c)| {
-                    if (i as u16) == state.cursor_x {
-                        None
-                    } else {
-                        Some(c)
-                    }
-                })
-                .collect(),
             ..state
         };
         Ok(state)
     }
 
-    fn delete_back(state: State) -> Result<State> {
+    fn delete_next(mut state: State) -> Result<State> {
+        state.delete_next();
+        Ok(state)
+    }
+
+    fn delete_back(mut state: State) -> Result<State> {
         if state.cursor_x == 0 {
             return Ok(state);
         }
-        let cursor_x = state.cursor_x.saturating_sub(1);
+        state.delete_back();
+        Ok(state)
+    }
+
+    fn delete_word_back(mut state: State) -> Result<State> {
+        state.delete_word_back();
+        Ok(state)
+    }
+
+    fn delete_to_end(mut state: State) -> Result<State> {
+        state.delete_to_end();
+        Ok(state)
+    }
+
+    fn delete_to_start(mut state: State) -> Result<State> {
+        state.delete_to_start();
+        Ok(state)
+    }
+
+    fn input_next(state: State) -> Result<State> {
         let state = State {
-            entry_cursor: false,
-            input: state
-                .input
-                .char_indices()
-                .filter_map(|(i, c)| {
-                    if (i as u16) == cursor_x {
-                        None
-                    } else {
-                        Some(c)
-                    }
-                })
-                .collect(),
-            cursor_x,
+            cursor_x: state.char_next(),
             ..state
         };
         Ok(state)
     }
 
-    fn input_next(state: State) -> Result<State> {
-        let len: u16 = state.input.len().try_into()?;
+    fn input_back(state: State) -> Result<State> {
         let state = State {
-            cursor_x: u16::min(len, state.cursor_x.saturating_add(1)),
+            cursor_x: state.char_back(),
             ..state
         };
         Ok(state)
     }
 
-    fn input_back(state: State) -> Result<State> {
+    fn input_word_next(state: State) -> Result<State> {
+        let state = State {
+            cursor_x: state.word_next(),
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn input_word_back(state: State) -> Result<State> {
+        let state = State {
+            cursor_x: state.word_back(),
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn input_start(state: State) -> Result<State> {
+        let state = State {
+            cursor_x: 0,
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn input_end(state: State) -> Result<State> {
         let state = State {
-            cursor_x: u16::max(0, state.cursor_x.saturating_sub(1)),
+            cursor_x: state.input.len(),
             ..state
         };
         Ok(state)
@@ -320,6 +766,40 @@ impl Keybinds {
         Ok(state)
     }
 
+    fn entry_page_next(state: State) -> Result<State> {
+        if state.entry_count == 0 {
+            return Ok(state);
+        }
+        let index = if state.entry_cursor {
+            state.entry_index
+        } else {
+            0
+        };
+        let state = State {
+            entry_cursor: true,
+            entry_index: (index + page_size(&state)).min(state.entry_count - 1),
+            ..state
+        };
+        Ok(state)
+    }
+
+    fn entry_page_back(state: State) -> Result<State> {
+        if state.entry_count == 0 {
+            return Ok(state);
+        }
+        let index = if state.entry_cursor {
+            state.entry_index
+        } else {
+            0
+        };
+        let state = State {
+            entry_cursor: true,
+            entry_index: index.saturating_sub(page_size(&state)),
+            ..state
+        };
+        Ok(state)
+    }
+
     fn entry_back(state: State) -> Result<State> {
         if state.entry_count == 0 {
             return Ok(state);
@@ -343,10 +823,11 @@ impl Keybinds {
             entry_cursor: false,
             entry_index: 0,
             menu_index: state
-                .menu_index
+                .tab_index()
                 .saturating_add(1)
                 .checked_rem(state.menu_count)
                 .ok_or_else(|| anyhow!("zero menus"))?,
+            menu_stack: Vec::new(),
             ..state
         };
         Ok(state)
@@ -358,17 +839,293 @@ impl Keybinds {
             cursor_x: 0,
             entry_cursor: false,
             entry_index: 0,
-            menu_index: if state.menu_index != 0 {
+            menu_index: if state.tab_index() != 0 {
                 state
-                    .menu_index
+                    .tab_index()
                     .saturating_sub(1)
                     .checked_rem(state.menu_count)
                     .ok_or_else(|| anyhow!("zero menus"))?
             } else {
                 state.menu_count - 1
             },
+            menu_stack: Vec::new(),
+            ..state
+        };
+        Ok(state)
+    }
+
+    /// Goes to the menu tab at an index, if there are that many tabs.
+    fn menu_jump(state: State, index: usize) -> Result<State> {
+        if index >= state.menu_count {
+            return Ok(state);
+        }
+        let state = State {
+            input: String::default(),
+            cursor_x: 0,
+            entry_cursor: false,
+            entry_index: 0,
+            menu_index: index,
+            menu_stack: Vec::new(),
             ..state
         };
         Ok(state)
     }
+
+    menu_jumps!(
+        menu_1 => 0,
+        menu_2 => 1,
+        menu_3 => 2,
+        menu_4 => 3,
+        menu_5 => 4,
+        menu_6 => 5,
+        menu_7 => 6,
+        menu_8 => 7,
+        menu_9 => 8
+    );
+
+    fn menu_parent(mut state: State) -> Result<State> {
+        state.leave_submenu();
+        Ok(state)
+    }
+}
+
+/// Returns the number of entries that the page keybinds move the entry cursor by, i.e. the
+/// number of entries that fit in the visible rows.
+fn page_size(state: &State) -> usize {
+    (state.entry_rows * state.entry_columns.max(1)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{self, Config, Entry, EntryKind, Menu},
+        frame::Frame,
+        layout, util,
+    };
+
+    /// Returns a menu of `count` entries shown `per_row` side by side, with a separator after
+    /// every fifth entry so that some rows aren't full.
+    fn menu(count: usize, per_row: usize) -> Menu {
+        let mut entries = Vec::new();
+        for i in 0..count {
+            entries.push((format!("entry {i}"), Entry::from(i.to_string())));
+            if i % 5 == 4 {
+                let separator = Entry {
+                    kind: EntryKind::Separator,
+                    ..Default::default()
+                };
+                entries.push((format!("separator {i}"), separator));
+            }
+        }
+        Menu {
+            entries,
+            columns: Some(per_row),
+            ..Default::default()
+        }
+    }
+
+    /// A menu drawn with a number of rows of entries, and the state of the interface.
+    struct Fixture {
+        config: Config,
+        menu: Menu,
+        rows: u16,
+        state: State,
+    }
+
+    impl Fixture {
+        fn new(count: usize, per_row: usize, rows: u16) -> Fixture {
+            let mut fixture = Fixture {
+                config: config::load_config(None, None).unwrap(),
+                menu: menu(count, per_row),
+                rows,
+                state: State::default(),
+            };
+            fixture.update();
+            fixture
+        }
+
+        /// Fits the state to the menu, as before each frame is drawn, and checks that the
+        /// selected entry is visible and matches the input.
+        fn update(&mut self) {
+            let (config, menu, state) = (&self.config, &self.menu, &mut self.state);
+            state.frame = Frame::sized(80, config.layout.rows().entries + self.rows + 1);
+            let entries = util::match_entries(state, menu, &config.options);
+            let layout = layout::layout(state, menu, config, &entries).unwrap();
+            util::update_viewport(state, &entries, &layout, &config.layout);
+            assert_eq!(state.entry_rows, usize::from(self.rows));
+            if state.entry_cursor {
+                let selected = util::selected_entry(state, &entries).unwrap();
+                assert!(state.input.is_empty() || selected.0.is_some());
+                let row = layout.selected_row(state, &entries);
+                let visible = state.entry_scroll..state.entry_scroll + state.entry_rows;
+                assert!(visible.contains(&row), "row {row} isn't in {visible:?}");
+            }
+        }
+
+        /// Presses a key `times` times, returning the selected entry after each press.
+        fn press(&mut self, times: usize, key: fn(State) -> Result<State>) -> Vec<usize> {
+            let mut selected = Vec::new();
+            for _ in 0..times {
+                self.state = key(self.state.clone()).unwrap();
+                self.update();
+                selected.push(self.state.entry_index);
+            }
+            selected
+        }
+    }
+
+    /// Returns every combination of the number of entries, entries per row, and rows that the
+    /// navigation is checked with.
+    fn sizes() -> impl Iterator<Item = (usize, usize, u16)> {
+        (1..=13).flat_map(|count| {
+            (1..=3).flat_map(move |per_row| (1..=4).map(move |rows| (count, per_row, rows)))
+        })
+    }
+
+    #[test]
+    fn entry_next_and_back_reach_every_entry_and_wrap() {
+        for (count, per_row, rows) in sizes() {
+            let case = format!("{count} entries, {per_row} per row, {rows} rows");
+            let mut fixture = Fixture::new(count, per_row, rows);
+            let next = fixture.press(count + 1, Keybinds::entry_next);
+            let expected: Vec<usize> = (0..count).chain([0]).collect();
+            assert_eq!(next, expected, "{case}");
+            assert_eq!(fixture.state.entry_scroll, 0, "{case}");
+
+            let mut fixture = Fixture::new(count, per_row, rows);
+            let back = fixture.press(count + 1, Keybinds::entry_back);
+            let expected: Vec<usize> = (0..count).rev().chain([count - 1]).collect();
+            assert_eq!(back, expected, "{case}");
+        }
+    }
+
+    #[test]
+    fn page_keys_reach_the_first_and_last_entries() {
+        for (count, per_row, rows) in sizes() {
+            let mut fixture = Fixture::new(count, per_row, rows);
+            let page = page_size(&fixture.state);
+            let next = fixture.press(count, Keybinds::entry_page_next);
+            assert_eq!(next.last(), Some(&(count - 1)));
+            assert!(next.windows(2).all(|pair| pair[1] - pair[0] <= page));
+            let back = fixture.press(count, Keybinds::entry_page_back);
+            assert_eq!(back.last(), Some(&0));
+        }
+    }
+
+    #[test]
+    fn filtering_keeps_every_match_reachable() {
+        let mut fixture = Fixture::new(30, 2, 3);
+        fixture.press(30, Keybinds::entry_next);
+        assert!(fixture.state.entry_scroll > 0);
+
+        // Only the entries with a 1 in their name match
+        fixture.state.insert_input("1");
+        fixture.update();
+        assert_eq!(fixture.state.entry_count, 12);
+        assert_eq!(fixture.state.entry_scroll, 0);
+        let next = fixture.press(12, Keybinds::entry_next);
+        assert_eq!(next, (0..12).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shrinking_menu_keeps_the_cursor_in_range() {
+        let mut fixture = Fixture::new(20, 1, 4);
+        fixture.press(20, Keybinds::entry_next);
+        assert_eq!(fixture.state.entry_index, 19);
+
+        // E.g. when the menu's source is refreshed while it's open
+        fixture.menu = menu(6, 1);
+        fixture.update();
+        assert_eq!(fixture.state.entry_index, 5);
+        let back = fixture.press(6, Keybinds::entry_back);
+        assert_eq!(back, [4, 3, 2, 1, 0, 5]);
+    }
+
+    /// Returns the key event of a key pressed without modifiers, other than shift for capitals.
+    fn key(code: KeyCode) -> KeyEvent {
+        let modifiers = match code {
+            KeyCode::Char(c) if c.is_uppercase() => KeyModifiers::SHIFT,
+            _ => KeyModifiers::NONE,
+        };
+        KeyEvent::new(code, modifiers)
+    }
+
+    /// Presses each key in normal mode, returning the state after the last one.
+    fn normal(state: State, keys: &str) -> State {
+        keys.chars().fold(state, |state, c| {
+            let (handled, state) = Keybinds::handle_vim(key(KeyCode::Char(c)), state).unwrap();
+            assert!(handled);
+            state
+        })
+    }
+
+    #[test]
+    fn escape_enters_normal_mode() {
+        let state = State {
+            entry_count: 3,
+            ..Default::default()
+        };
+        let (handled, state) = Keybinds::handle_vim(key(KeyCode::Char('j')), state).unwrap();
+        assert!(!handled && !state.normal_mode);
+        let alt_escape = KeyEvent::new(KeyCode::Esc, KeyModifiers::ALT);
+        let (handled, state) = Keybinds::handle_vim(alt_escape, state).unwrap();
+        assert!(!handled && !state.normal_mode);
+
+        let (handled, state) = Keybinds::handle_vim(key(KeyCode::Esc), state).unwrap();
+        assert!(handled && state.normal_mode && state.entry_cursor);
+
+        // The entry cursor stays hidden if there's nothing to select
+        let (_, state) = Keybinds::handle_vim(key(KeyCode::Esc), State::default()).unwrap();
+        assert!(state.normal_mode && !state.entry_cursor);
+    }
+
+    #[test]
+    fn gg_and_capital_g_select_the_first_and_last_entries() {
+        let state = State {
+            normal_mode: true,
+            entry_count: 5,
+            entry_index: 2,
+            ..Default::default()
+        };
+        let state = normal(state, "g");
+        assert_eq!((state.pending_key, state.entry_index), (Some('g'), 2));
+        let state = normal(state, "g");
+        assert_eq!((state.pending_key, state.entry_index), (None, 0));
+        assert!(state.entry_cursor);
+        let state = normal(state, "G");
+        assert_eq!(state.entry_index, 4);
+
+        // A pending g is dropped by any other key
+        let state = normal(state, "gk");
+        assert_eq!((state.pending_key, state.entry_index), (None, 3));
+        assert!(state.normal_mode);
+    }
+
+    #[test]
+    fn insert_keys_leave_normal_mode_with_the_cursor_on_a_char() {
+        let at = |cursor: usize| {
+            let mut state = State {
+                normal_mode: true,
+                input: "aé日".to_string(),
+                ..Default::default()
+            };
+            state.set_cursor(cursor);
+            state
+        };
+        for (keys, cursor, expected) in [
+            ("i", 1, 1),
+            ("a", 1, 3),
+            ("a", 3, 6),
+            ("a", 6, 6),
+            ("I", 3, 0),
+            ("A", 1, 6),
+            ("A", 0, 6),
+        ] {
+            let state = normal(at(cursor), keys);
+            assert!(!state.normal_mode, "{keys} at {cursor}");
+            assert_eq!(state.cursor(), expected, "{keys} at {cursor}");
+            assert!(state.input.is_char_boundary(state.cursor_x));
+        }
+    }
 }
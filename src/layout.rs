@@ -0,0 +1,447 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Arranges entries into rows, with one or more entries side by side in each row, and the rows
+//! of the interface as set by the `[layout]` section of the config.
+
+use std::ops::Range;
+
+use serde::Deserialize;
+
+use crate::{
+    config::{Config, EntryKind, Menu},
+    draw::{ICON_COLUMN_WIDTH, KEY_COLUMN_WIDTH},
+    options::ValueAlign,
+    state::State,
+    util::{self, MatchedEntry},
+};
+
+/// The rows and spacing of the interface.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct LayoutOptions {
+    /// Indicates that the menu line, with the menu tabs, is shown.
+    pub(crate) menu_line: bool,
+
+    /// The number of empty rows between the menu line and the prompt.
+    pub(crate) menu_gap: u16,
+
+    /// The number of empty rows between the line under the prompt and the entries.
+    pub(crate) entries_gap: u16,
+
+    /// The number of columns between elements on the same line.
+    pub(crate) spacing: u16,
+
+    /// A line shown above the menu line, unless it's empty. `{menu}` is replaced with the name
+    /// of the current menu, and `{count}` with the number of matching entries.
+    pub(crate) header: String,
+
+    /// A line shown in the bottom row, unless it's empty. Takes the same placeholders as the
+    /// header.
+    pub(crate) footer: String,
+
+    /// How the menu tabs are separated and decorated.
+    pub(crate) tabs: TabsOptions,
+}
+
+impl LayoutOptions {
+    /// Returns the rows that each part of the interface is drawn in.
+    pub(crate) fn rows(&self) -> Rows {
+        let top = u16::from(!self.header.is_empty());
+        let prompt = if self.menu_line {
+            top + 1 + self.menu_gap
+        } else {
+            top
+        };
+        Rows {
+            header: (top > 0).then_some(0),
+            menu_line: self.menu_line.then_some(top),
+            prompt,
+            preview: prompt + 1,
+            entries: prompt + 2 + self.entries_gap,
+            footer: u16::from(!self.footer.is_empty()),
+        }
+    }
+}
+
+/// How the menu tabs are separated and decorated, set by the `[layout.tabs]` section.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct TabsOptions {
+    /// Drawn between adjacent tabs in place of the spacing, unless it's empty.
+    pub(crate) separator: String,
+
+    /// The number of columns on each side of a tab's name, drawn in the tab's style.
+    pub(crate) padding: u16,
+
+    /// Drawn before the name of the current tab, e.g. `[`. Other tabs have blank space in its
+    /// place, so that the tabs don't shift when switching between them.
+    pub(crate) active_prefix: String,
+
+    /// Drawn after the name of the current tab, e.g. `]`.
+    pub(crate) active_suffix: String,
+}
+
+impl TabsOptions {
+    /// Returns the text of a tab, with its padding and decorations.
+    pub(crate) fn label(&self, name: &str, active: bool) -> String {
+        let padding = " ".repeat(self.padding.into());
+        let (prefix, suffix) = match active {
+            true => (self.active_prefix.clone(), self.active_suffix.clone()),
+            false => (
+                " ".repeat(util::width(&self.active_prefix)),
+                " ".repeat(util::width(&self.active_suffix)),
+            ),
+        };
+        format!("{prefix}{padding}{name}{padding}{suffix}")
+    }
+
+    /// Returns the number of columns between adjacent tabs, which is the `spacing` unless
+    /// there's a separator.
+    fn gap(&self, spacing: u16) -> anyhow::Result<u16> {
+        match self.separator.is_empty() {
+            true => Ok(spacing),
+            false => Ok(util::width(&self.separator).try_into()?),
+        }
+    }
+}
+
+/// The rows that each part of the interface is drawn in.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Rows {
+    /// The row of the header, unless it's empty.
+    pub(crate) header: Option<u16>,
+
+    /// The row of the menu tabs, unless they're hidden.
+    pub(crate) menu_line: Option<u16>,
+
+    /// The row of the prompt and the input.
+    pub(crate) prompt: u16,
+
+    /// The row under the prompt, which shows the value preview, the message, and key
+    /// descriptions.
+    pub(crate) preview: u16,
+
+    /// The first row of entries.
+    pub(crate) entries: u16,
+
+    /// The number of rows at the bottom that are taken by the footer.
+    pub(crate) footer: u16,
+}
+
+/// The columns of an entry, which are aligned across all entries.
+pub(crate) struct Columns {
+    /// Indicates that hotkeys, or quick select keys, are shown in the first column.
+    pub(crate) key: bool,
+
+    /// Indicates that the first column shows the quick select keys of the visible entries,
+    /// instead of hotkeys.
+    pub(crate) quick_select: bool,
+
+    /// Indicates that icons are shown before the names.
+    pub(crate) icon: bool,
+
+    /// Column where the names start.
+    pub(crate) name: u16,
+
+    /// Column where the descriptions start.
+    pub(crate) desc: u16,
+
+    /// The number of columns between the name, description, and value.
+    pub(crate) spacing: u16,
+
+    /// Indicates that values are shown.
+    pub(crate) value: bool,
+
+    /// Where values are placed.
+    pub(crate) value_align: ValueAlign,
+
+    /// Column where values start if they're left-aligned, unless the name or description of an
+    /// entry extends past it.
+    pub(crate) value_x: u16,
+
+    /// The maximum width of values. Unlimited if zero.
+    pub(crate) value_max_width: usize,
+
+    /// Indicates that the entry cursor highlights the whole row of the selected entry, instead
+    /// of just its name.
+    pub(crate) full_line_cursor: bool,
+}
+
+/// Where each entry is drawn.
+pub(crate) struct Layout {
+    /// The columns within each entry.
+    pub(crate) columns: Columns,
+
+    /// The number of entries side by side in each row.
+    pub(crate) per_row: usize,
+
+    /// The width of each entry. Entries fill the whole row if there's only one per row.
+    pub(crate) cell_width: u16,
+
+    /// The row and the position in that row of each entry.
+    pub(crate) cells: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    /// Returns the row of the selected entry.
+    pub(crate) fn selected_row(&self, state: &State, entries: &[MatchedEntry]) -> usize {
+        entries
+            .iter()
+            .zip(&self.cells)
+            .filter(|(entry, _)| util::is_selectable(state, entry))
+            .nth(state.entry_index)
+            .map(|(_, cell)| cell.0)
+            .unwrap_or_default()
+    }
+
+    /// Returns the selectable index of the `n`th selectable entry in the visible rows, which is
+    /// submitted by the `n`th quick select key.
+    pub(crate) fn quick_select_entry(
+        &self,
+        state: &State,
+        entries: &[MatchedEntry],
+        n: usize,
+    ) -> Option<usize> {
+        let visible = state.entry_scroll..state.entry_scroll + state.entry_rows;
+        entries
+            .iter()
+            .zip(&self.cells)
+            .filter(|(entry, _)| util::is_selectable(state, entry))
+            .enumerate()
+            .filter(|(_, (_, cell))| visible.contains(&cell.0))
+            .nth(n)
+            .map(|(index, _)| index)
+    }
+
+    /// Returns the selectable index of the entry drawn at `column` in the visible entry `row`.
+    pub(crate) fn entry_at(
+        &self,
+        state: &State,
+        entries: &[MatchedEntry],
+        row: usize,
+        column: u16,
+    ) -> Option<usize> {
+        let row = row + state.entry_scroll;
+        let column = usize::from(column / self.cell_width.max(1));
+        entries
+            .iter()
+            .zip(&self.cells)
+            .filter(|(entry, _)| util::is_selectable(state, entry))
+            .position(|(_, cell)| *cell == (row, column))
+    }
+}
+
+/// Marks that menu tabs are scrolled out of view on the left of the menu line.
+pub(crate) const TABS_HIDDEN_LEFT: &str = "<";
+
+/// Marks that menu tabs are scrolled out of view on the right of the menu line.
+pub(crate) const TABS_HIDDEN_RIGHT: &str = ">";
+
+/// Precedes each submenu in the breadcrumb trail.
+const BREADCRUMB_SEPARATOR: &str = "▸ ";
+
+/// Where the menu tabs are drawn on the menu line.
+pub(crate) struct Tabs {
+    /// The columns that each menu tab spans. Tabs that are scrolled out of view span no columns.
+    pub(crate) ranges: Vec<Range<u16>>,
+
+    /// Indicates that tabs are scrolled out of view on the left.
+    pub(crate) hidden_left: bool,
+
+    /// Indicates that tabs are scrolled out of view on the right.
+    pub(crate) hidden_right: bool,
+
+    /// The column after the last tab in view.
+    pub(crate) end: u16,
+
+    /// The text of each crumb in the breadcrumb trail of the open submenus, outermost first, and
+    /// the columns it spans. The trail is cut off at the end of the menu line, and left out if
+    /// tabs are scrolled out of view on the right.
+    pub(crate) breadcrumbs: Vec<(String, Range<u16>)>,
+}
+
+/// Arranges the menu tabs on the menu line. If they don't all fit, the tab line is scrolled just
+/// far enough that the current tab is in view, leaving room for the markers of the tabs that
+/// aren't.
+pub(crate) fn tabs(
+    menus: &[(String, Menu)],
+    layout: &LayoutOptions,
+    state: &State,
+) -> anyhow::Result<Tabs> {
+    let options = &layout.tabs;
+    let widths = menus
+        .iter()
+        .take(state.menu_count)
+        .map(|menu| u16::try_from(util::width(&options.label(&menu.0, false))))
+        .collect::<Result<Vec<u16>, _>>()?;
+    let (width, spacing) = (state.frame.size().0, layout.spacing);
+    let gap = options.gap(spacing)?;
+    if widths.is_empty() {
+        return Ok(Tabs {
+            ranges: Vec::new(),
+            hidden_left: false,
+            hidden_right: false,
+            end: 0,
+            breadcrumbs: Vec::new(),
+        });
+    }
+    let last = widths.len() - 1;
+    let current = state.tab_index().min(last);
+
+    // Each marker takes its width and the spacing between it and the tabs
+    let left_start = u16::try_from(util::width(TABS_HIDDEN_LEFT))? + spacing;
+    let right_space = u16::try_from(util::width(TABS_HIDDEN_RIGHT))? + spacing;
+    let room = |i: usize| match i < last {
+        true => width.saturating_sub(right_space),
+        false => width,
+    };
+    let fits = |first: usize| {
+        let start = if first > 0 { left_start } else { 0 };
+        let end = widths[first..=current]
+            .iter()
+            .fold(start, |x, width| x + width + gap)
+            - gap;
+        end <= room(current)
+    };
+    let first = (0..current).find(|&first| fits(first)).unwrap_or(current);
+
+    let mut tabs = Tabs {
+        ranges: vec![0..0; widths.len()],
+        hidden_left: first > 0,
+        hidden_right: false,
+        end: 0,
+        breadcrumbs: Vec::new(),
+    };
+    let mut x = if first > 0 { left_start } else { 0 };
+    for (i, width) in widths.iter().enumerate().skip(first) {
+        // The first tab in view is always shown, even if the menu line is too narrow for it
+        if i > first && x + width > room(i) {
+            tabs.hidden_right = true;
+            break;
+        }
+        tabs.ranges[i] = x..x + width;
+        tabs.end = x + width;
+        x += width + gap;
+    }
+    if tabs.hidden_right || state.menu_stack.is_empty() {
+        return Ok(tabs);
+    }
+
+    let mut x = tabs.end + spacing;
+    let submenus = state.menu_stack.iter().skip(1).chain([&state.menu_index]);
+    for index in submenus {
+        let name = &menus
+            .get(*index)
+            .ok_or_else(|| anyhow::anyhow!("invalid menu index"))?
+            .0;
+        let crumb = format!("{BREADCRUMB_SEPARATOR}{name} ");
+        let crumb = util::truncate(&crumb, width.saturating_sub(x).into());
+        let crumb_width: u16 = util::width(crumb).try_into()?;
+        tabs.breadcrumbs
+            .push((crumb.to_string(), x..x + crumb_width));
+        x += crumb_width;
+    }
+    Ok(tabs)
+}
+
+/// Arranges the entries of a menu. Separators and labels always take a row of their own, and
+/// other entries fill the rows from left to right.
+///
+/// The number of entries per row comes from the menu's `columns`, or else the `columns` option.
+/// If it's zero, as many entries as fit side by side are shown in each row.
+pub(crate) fn layout(
+    state: &State,
+    menu: &Menu,
+    config: &Config,
+    entries: &[MatchedEntry],
+) -> anyhow::Result<Layout> {
+    let (options, spacing) = (&config.options, config.layout.spacing);
+    let width = util::entries_width(state);
+
+    // Hotkeys are only active while the input is empty, and quick select takes their place
+    // otherwise
+    let hotkeys = menu.hotkeys && state.input.is_empty();
+    let quick_select = options.quick_select && !hotkeys;
+    let key = hotkeys || quick_select;
+    let icon = entries.iter().any(|entry| entry.2.icon.is_some());
+    let name = if key { KEY_COLUMN_WIDTH } else { 0 } + if icon { ICON_COLUMN_WIDTH } else { 0 };
+
+    // Descriptions are aligned in a column after the longest name that has one
+    let desc: u16 = entries
+        .iter()
+        .filter(|entry| entry.2.desc.is_some())
+        .map(|entry| util::width(&entry.1))
+        .max()
+        .unwrap_or_default()
+        .try_into()?;
+    let desc = name + desc + spacing;
+
+    // Left-aligned values are aligned in a column after the longest name or description, but
+    // long descriptions are cut off to leave room for the values
+    let value_x = entries
+        .iter()
+        .filter(|entry| entry.2.kind.is_submittable())
+        .map(|entry| match &entry.2.desc {
+            Some(entry_desc) => usize::from(desc) + util::width(entry_desc),
+            None => usize::from(name) + util::width(&entry.1),
+        })
+        .max()
+        .unwrap_or_default()
+        + usize::from(spacing);
+    let columns = Columns {
+        key,
+        quick_select,
+        icon,
+        name,
+        desc,
+        spacing,
+        value: util::show_values(state, menu, options),
+        value_align: options.value_align,
+        value_x: value_x.min(usize::from(width) * 2 / 3).try_into()?,
+        value_max_width: options.value_max_width,
+        full_line_cursor: options.full_line_cursor,
+    };
+
+    let per_row = match menu.columns.unwrap_or(options.columns) {
+        0 => {
+            let longest: u16 = entries
+                .iter()
+                .filter(|entry| entry.2.kind.is_submittable())
+                .map(|entry| util::width(&entry.1))
+                .max()
+                .unwrap_or_default()
+                .try_into()?;
+            usize::from(width / name.saturating_add(longest).saturating_add(spacing).max(1))
+        }
+        n => n,
+    }
+    .max(1);
+    let cell_width = width / u16::try_from(per_row).unwrap_or(u16::MAX).max(1);
+
+    let mut cells = Vec::with_capacity(entries.len());
+    let (mut row, mut column) = (0, 0);
+    for entry in entries {
+        match entry.2.kind {
+            EntryKind::Normal | EntryKind::Submenu | EntryKind::Link => {
+                if column == per_row {
+                    row += 1;
+                    column = 0;
+                }
+                cells.push((row, column));
+                column += 1;
+            }
+            EntryKind::Separator | EntryKind::Label => {
+                if column > 0 {
+                    row += 1;
+                }
+                cells.push((row, 0));
+                row += 1;
+                column = 0;
+            }
+        }
+    }
+
+    Ok(Layout {
+        columns,
+        per_row,
+        cell_width,
+        cells,
+    })
+}
@@ -4,10 +4,13 @@
 macro_rules! set_style {
     ($style:expr) => {
         crossterm::style::SetStyle(crossterm::style::ContentStyle {
-            foreground_color: Some($style.fg.0),
-            background_color: Some($style.bg.0),
-            underline_color: None,
-            attributes: $style.attrs.0,
+            foreground_color: $crate::theme::color(&$style.fg),
+            background_color: $crate::theme::color(&$style.bg),
+            underline_color: $style
+                .underline_color
+                .as_ref()
+                .and_then($crate::theme::color),
+            attributes: $crate::theme::attributes(&$style.attrs),
         })
     };
 }
@@ -25,3 +28,15 @@ macro_rules! handle_key_event {
         }
     };
 }
+
+/// Defines the keybind handlers that go to a menu tab by its index, with `Keybinds::menu_jump`.
+#[macro_export]
+macro_rules! menu_jumps {
+    ( $( $bind:ident => $index:expr ),+ ) => {
+        $(
+            fn $bind(state: State) -> Result<State> {
+                Keybinds::menu_jump(state, $index)
+            }
+        )+
+    };
+}
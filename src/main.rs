@@ -2,53 +2,201 @@
 //! A multi-page fuzzy launcher for your terminal.
 
 use std::{
-    io::{self, stderr, stdout, Write},
-    process::{self, Command, Stdio},
-    time::Duration,
+    env,
+    io::{self, stderr, BufWriter, Write},
+    path::Path,
+    process,
+    time::{Duration, Instant},
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use args::Args;
 use clap::Parser;
 use crossterm::{
     cursor::{MoveTo, SavePosition},
-    event::{poll, read, DisableFocusChange, EnableFocusChange, Event},
-    execute,
+    event::{
+        poll, read, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture,
+        Event, KeyCode, KeyEvent, KeyModifiers,
+    },
+    execute, queue,
     style::Print,
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType, SetTitle},
 };
 
 mod args;
+mod check;
+mod clipboard;
 mod config;
+mod dmenu;
 mod draw;
+mod exec;
+mod frame;
+mod history;
+mod init;
+mod instance;
 mod keybinds;
+mod layout;
 mod macros;
+mod markup;
+mod mouse;
+mod options;
+mod output;
+mod palette;
+mod popup;
+mod preview;
+mod profile;
+mod promote;
+mod record;
+mod remote;
+mod run;
+mod scrollback;
+mod session;
+mod sources;
 mod state;
+mod stats;
+mod status;
 mod theme;
+mod translit;
 mod util;
+mod vars;
+mod watch;
 
 use crate::{
+    config::EntryKind,
     draw::draw,
+    frame::Frame,
+    instance::Instance,
+    preview::Previewer,
+    scrollback::{Scrollback, SCROLLBACK_ROWS},
     state::{Action, State},
+    watch::Watcher,
 };
 
+/// Capacity of the buffer that frames are written to, which is large enough that a frame of a
+/// big terminal is written in a single syscall.
+const FRAME_BUFFER_SIZE: usize = 64 * 1024;
+
 fn main() {
     let res: Result<()> = (|| {
-        let mut tty = util::tty()?;
         let args = args::Args::parse();
-        let mut config = config::load_config(args.config.clone())?;
+        if args.profile {
+            profile::enable();
+        }
+        sources::register_builtins()?;
+        remote::set_offline(args.offline);
+        history::set_read_only(args.demo);
+        match &args.command {
+            Some(args::Command::Check { config }) => {
+                if !check::check(
+                    config.clone().or_else(|| args.config.clone()),
+                    args.theme.as_deref(),
+                )? {
+                    process::exit(1);
+                }
+                return Ok(());
+            }
+            Some(args::Command::Init { config, force }) => {
+                let path = init::init(config.clone(), *force)?;
+                println!("Wrote {}", path.display());
+                return Ok(());
+            }
+            Some(args::Command::Popup {
+                config,
+                args: menu_args,
+            }) => {
+                popup::popup(config.clone().or_else(|| args.config.clone()), menu_args)?;
+                return Ok(());
+            }
+            Some(args::Command::Run { config, entries }) => {
+                run::run(&args, config.clone(), entries)?;
+                return Ok(());
+            }
+            Some(args::Command::RecordKeybind) => {
+                println!("{}", record::record_keybind()?);
+                return Ok(());
+            }
+            Some(args::Command::Stats { config, format }) => {
+                stats::stats(config.clone().or_else(|| args.config.clone()), *format)?;
+                return Ok(());
+            }
+            None => {}
+        }
+
+        let instance = if args.single_instance {
+            match instance::claim()? {
+                Some(instance) => Some(instance),
+                None => return Ok(()),
+            }
+        } else {
+            None
+        };
+
+        // Frames are written to the terminal all at once, when the buffer is flushed after drawing
+        let mut tty = BufWriter::with_capacity(FRAME_BUFFER_SIZE, util::tty(args.tty.as_deref())?);
+        let mut config = if args.session_chooser {
+            session::config(&args)?
+        } else if args.dmenu {
+            dmenu::config(&args)?
+        } else {
+            config::load_config(
+                Some(config::config_path(args.config.clone())?),
+                args.theme.as_deref(),
+            )?
+        };
+        override_options(&args, &mut config);
+        theme::set_fallbacks(&config.options.attr_fallbacks);
+        // See https://no-color.org
+        let no_color = env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty());
+        theme::set_no_color(args.no_color || no_color);
         util::sort_menus(&mut config);
+        util::assign_frecency(&mut config);
+        util::insert_recent_menu(&mut config);
+        let start_menu = match &args.menu {
+            Some(name) => Some(menu_position(&config, name)?),
+            None => None,
+        };
         execute!(tty, Clear(ClearType::All), EnableFocusChange)?;
+        if config.options.mouse {
+            execute!(tty, EnableMouseCapture)?;
+        }
+        if !config.options.title.is_empty() {
+            // Save the terminal's title on its title stack, in terminals that support xterm's
+            // window manipulation sequences
+            execute!(tty, Print("\x1b[22;0t"))?;
+        }
+        if config.theme.cursor != theme::CursorStyle::default() {
+            execute!(tty, config.theme.cursor)?;
+        }
         enable_raw_mode()?;
-        let selection = interact(&mut tty, &args, &mut config)?;
+        let selection = interact(&mut tty, &args, &mut config, instance.as_ref(), start_menu);
         disable_raw_mode()?;
-        submit(&mut tty, &args, selection)?;
+        if config.options.mouse {
+            execute!(tty, DisableMouseCapture)?;
+        }
+        if !config.options.title.is_empty() {
+            // Restore the saved title
+            execute!(tty, Print("\x1b[23;0t"))?;
+        }
+        if config.theme.cursor != theme::CursorStyle::default() {
+            // Terminals can't report the cursor's shape, so the terminal's own is restored
+            execute!(tty, theme::CursorStyle::default())?;
+        }
+        let (selection, action) = selection?;
+        if args.session_chooser {
+            execute!(tty, Clear(ClearType::All), MoveTo(0, 0), DisableFocusChange)?;
+            session::exec(&selection)?;
+        }
+        let action = action.and_then(|name| Some((config.actions.get(&name)?.clone(), name)));
+        submit(&mut tty, &args, selection, action)?;
         execute!(tty, Clear(ClearType::All), MoveTo(0, 0), DisableFocusChange)?;
         Ok(())
     })();
 
     match res {
-        Ok(_) => process::exit(0),
+        Ok(_) => {
+            let _ = profile::report(&mut stderr());
+            process::exit(0)
+        }
         Err(e) => {
             let _ = writeln!(stderr(), "{e:?}");
             process::exit(1);
@@ -57,103 +205,749 @@ fn main() {
 }
 
 /// Handles event polling, state management, and drawing the interface.
-fn interact(tty: &mut impl io::Write, args: &Args, config: &mut config::Config) -> Result<String> {
+fn interact(
+    tty: &mut impl io::Write,
+    args: &Args,
+    config: &mut config::Config,
+    instance: Option<&Instance>,
+    start_menu: Option<usize>,
+) -> Result<(String, Option<String>)> {
     let mut first = true;
-    let mut state = State::default();
-    state.menu_count = config.menus.len().try_into()?;
+    let menu_count = util::count_tabs(config);
+    let mut state = State {
+        menu_count,
+        menu_index: last_menu(config, menu_count).unwrap_or_default(),
+        scrollback_rows: if args.stay_open { SCROLLBACK_ROWS } else { 0 },
+        ..Default::default()
+    };
+    match start_menu {
+        Some(index) if index < menu_count => state.menu_index = index,
+        // Submenus are opened over the starting tab, so that backing out leads to the tabs
+        Some(index) => state.enter_submenu(index),
+        None => {}
+    }
+    if let Some(query) = &args.query {
+        state.insert_input(query);
+    }
+    let mut scrollback = Scrollback::default();
+    let mut previewer = Previewer::default();
+    let mut last_exit: Option<Instant> = None;
+    let mut last_key = Instant::now();
+    let mut last_title = String::new();
+    let auto_close = args.auto_close_secs.map(Duration::from_secs);
+    let watcher = if args.watch {
+        Some(Watcher::new(&config::config_path(args.config.clone())?)?)
+    } else {
+        None
+    };
 
     loop {
         let last_state = state.clone();
         let mut force_redraw = false;
+        let mut key_start = None;
 
         // Handle events
         if !first {
-            if !poll(Duration::from_millis(100))? {
-                continue;
-            }
-
-            match read()? {
-                Event::Resize(_, _) => {
+            if scrollback.poll() {
+                force_redraw = true;
+            } else if let Some(output) = previewer.poll() {
+                state.preview_output = Some(output);
+            } else if let Some(watcher) = watcher.as_ref().filter(|watcher| watcher.changed()) {
+                force_redraw = reload(args, config, &mut state, watcher.path());
+            } else if instance.is_some_and(Instance::raise_requested) {
+                // Deiconify and raise the window, in terminals that support xterm's window
+                // manipulation sequences
+                queue!(tty, Print("\x1b[1t\x1b[5t"))?;
+                force_redraw = true;
+            } else if !poll(Duration::from_millis(100))? {
+                if auto_close.is_some_and(|auto_close| last_key.elapsed() >= auto_close) {
+                    break;
+                }
+                let (name, menu) = config
+                    .menus
+                    .get_mut(state.menu_index)
+                    .ok_or_else(|| anyhow!("invalid menu index"))?;
+                if sources::refresh(name, menu)? {
                     force_redraw = true;
+                } else {
+                    continue;
                 }
-                Event::FocusLost => {
-                    if args.transient {
-                        break;
+            } else {
+                match read()? {
+                    Event::Resize(_, _) => {
+                        force_redraw = true;
                     }
+                    Event::FocusLost if args.transient => break,
+                    Event::Key(_) if state.help => {
+                        last_key = Instant::now();
+                        state.help = false;
+                    }
+                    Event::Key(event) if state.describing_key => {
+                        last_key = Instant::now();
+                        key_start = Some(last_key);
+                        state.key_description = Some(describe_key(config, &state, event)?);
+                        state.describing_key = false;
+                    }
+                    Event::Key(event) => {
+                        last_key = Instant::now();
+                        key_start = Some(last_key);
+                        state.key_description = None;
+                        state.custom_action = None;
+                        queue!(tty, SavePosition)?;
+                        let entry_key = match hotkey(config, &state, event)? {
+                            Some(entry_index) => Some(entry_index),
+                            None => quick_select(config, &state, event)?,
+                        };
+                        state = match entry_key {
+                            Some(entry_index) => State {
+                                entry_cursor: true,
+                                entry_index,
+                                action: Action::Submit,
+                                ..state
+                            },
+                            None => match custom_action(config, event) {
+                                Some(name) => State {
+                                    action: Action::Submit,
+                                    custom_action: Some(name),
+                                    ..state
+                                },
+                                None if config.options.vim_mode => {
+                                    match keybinds::Keybinds::handle_vim(event, state)? {
+                                        (true, state) => state,
+                                        (false, state) => config.keybinds.handle(event, state)?,
+                                    }
+                                }
+                                None => config.keybinds.handle(event, state)?,
+                            },
+                        };
+                        if state.action == Action::Exit && config.options.exit_backs_out {
+                            // A quick second press exits from anywhere
+                            let double_exit = last_exit.is_some_and(|t| {
+                                t.elapsed() < Duration::from_millis(config.options.double_exit_ms)
+                            });
+                            last_exit = Some(Instant::now());
+                            if !double_exit && state.back_out() {
+                                state.action = Action::Clear;
+                            }
+                        } else {
+                            last_exit = None;
+                        }
+                        if state.action == Action::Submit {
+                            if let Some(menu_index) = submenu(config, &state)? {
+                                state.enter_submenu(menu_index);
+                                state.action = Action::Clear;
+                            }
+                        }
+                        if state.action == Action::MatchMode {
+                            let menu = &config
+                                .menus
+                                .get(state.menu_index)
+                                .ok_or_else(|| anyhow!("invalid menu index"))?
+                                .1;
+                            let mode = util::match_mode(&state, menu, &config.options);
+                            state.match_mode = Some(mode.next());
+                            state.entry_cursor = false;
+                            state.entry_index = 0;
+                            state.action = Action::Clear;
+                        }
+                        if state.action == Action::ToggleValues {
+                            let menu = &config
+                                .menus
+                                .get(state.menu_index)
+                                .ok_or_else(|| anyhow!("invalid menu index"))?
+                                .1;
+                            state.show_values =
+                                Some(!util::show_values(&state, menu, &config.options));
+                            state.action = Action::Clear;
+                        }
+                        if state.action == Action::Disable {
+                            // The config may not be writable, e.g. if it isn't TOML, which
+                            // shouldn't close the menu
+                            if let Err(e) = disable(args, config, &state) {
+                                state.key_description = Some(format!("{e:#}"));
+                            }
+                            state.entry_cursor = false;
+                            state.entry_index = 0;
+                            state.action = Action::Clear;
+                            force_redraw = true;
+                        }
+                        if state.action == Action::Promote {
+                            if let Err(e) = promote(args, config, &state) {
+                                state.key_description = Some(format!("{e:#}"));
+                            }
+                            state.action = Action::Clear;
+                            force_redraw = true;
+                        }
+                        if state.action == Action::Forget {
+                            if let Err(e) = forget(config, &mut state) {
+                                state.key_description = Some(format!("{e:#}"));
+                            }
+                            state.action = Action::Clear;
+                            force_redraw = true;
+                        }
+                        if state.action == Action::Yank {
+                            yank(tty, config, &mut state)?;
+                            state.action = Action::Clear;
+                        }
+                        if state.action == Action::Complete {
+                            complete(config, &mut state)?;
+                            state.action = Action::Clear;
+                        }
+                        if state.action == Action::Reload {
+                            let path = config::config_path(args.config.clone())?;
+                            if reload(args, config, &mut state, &path) {
+                                force_redraw = true;
+                            } else {
+                                state.key_description =
+                                    Some("The config failed to load, so it wasn't reloaded".into());
+                            }
+                            state.action = Action::Clear;
+                        }
+                        if state.action == Action::Paste {
+                            match clipboard::paste() {
+                                Ok(text) => state.insert_input(&text),
+                                Err(e) => state.key_description = Some(format!("{e:#}")),
+                            }
+                            state.action = Action::Clear;
+                        }
+                    }
+                    Event::Mouse(event) if config.options.mouse => {
+                        last_key = Instant::now();
+                        state = mouse::handle(config, state, event)?;
+                        if state.action == Action::Submit {
+                            if let Some(menu_index) = submenu(config, &state)? {
+                                state.enter_submenu(menu_index);
+                                state.action = Action::Clear;
+                            }
+                        }
+                    }
+                    _ => {}
                 }
-                Event::Key(event) => {
-                    execute!(tty, SavePosition)?;
-                    state = config.keybinds.handle(event, state)?;
-                }
-                _ => {}
             }
         }
 
         // Update + draw
         if state != last_state || first || force_redraw {
             // Update
+            state.frame = Frame::new(&config.options)?;
             let menu = config
                 .menus
                 .get(state.menu_index)
                 .ok_or_else(|| anyhow!("invalid menu index"))?;
-            let entries = util::match_entries(&state.input, &menu.1.entries);
-            state.entry_count = util::count_selectable_entries(&state, &entries);
+            let entries = util::match_entries(&state, &menu.1, &config.options);
+            (state.preview_width, state.preview_rows) =
+                util::preview_size(&state.frame, &config.options);
+            let layout = layout::layout(&state, &menu.1, config, &entries)?;
+            util::update_viewport(&mut state, &entries, &layout, &config.layout);
+            if state.preview_width > 0 || state.preview_rows > 0 {
+                let command = util::preview_command(&menu.1, &config.options);
+                let selection = util::selected_entry(&state, &entries);
+                match (command, selection) {
+                    (Some(command), Some(selection)) if state.entry_count > 0 => {
+                        if previewer.request(command, &selection.2.value) {
+                            state.preview_output = None;
+                        }
+                    }
+                    _ => {
+                        previewer.clear();
+                        state.preview_output = None;
+                    }
+                }
+            }
 
             // Handle state action
             match state.action {
                 Action::None => {}
                 Action::Exit => break,
                 Action::Clear => {
-                    execute!(tty, Clear(ClearType::All))?;
+                    queue!(tty, Clear(ClearType::All))?;
                 }
+                // Handled along with the key event
+                Action::Promote
+                | Action::MatchMode
+                | Action::Disable
+                | Action::ToggleValues
+                | Action::Paste
+                | Action::Yank
+                | Action::Forget
+                | Action::Complete
+                | Action::Reload => {}
                 Action::Submit => {
                     if state.entry_count > 0 {
-                        let selection = entries
-                            .get(state.entry_index)
+                        let selection = util::selected_entry(&state, &entries)
                             .ok_or_else(|| anyhow!("selection index out of bounds"))?;
-                        return Ok(selection.2.clone());
+                        if !selection.2.info && !args.session_chooser && !args.dmenu {
+                            // The history only affects sorting, so failing to record to it
+                            // isn't an error
+                            let origin = selection.2.origin.as_ref().unwrap_or(&menu.0);
+                            let _ = history::record(origin, &selection.1);
+                        }
+                        let exec_with = state
+                            .custom_action
+                            .as_ref()
+                            .and_then(|name| config.actions.get(name))
+                            .and_then(|action| action.exec_with.as_deref());
+                        if selection.2.info {
+                            // Informational entries can't be submitted
+                        } else if let (Some(exec_with), true) = (exec_with, args.stay_open) {
+                            let spawned =
+                                exec::execute_with(exec_with, &selection.2.value)?.spawn();
+                            let timeout = selection.2.timeout_secs.map(Duration::from_secs);
+                            scrollback.push(selection.1.clone(), spawned, timeout);
+                        } else if exec_with.is_some() {
+                            save_last_menu(args, config, &state);
+                            return Ok((selection.2.value.clone(), state.custom_action));
+                        } else if selection.2.kind == EntryKind::Link {
+                            let opener = &config.options.opener;
+                            let spawned = exec::open_link(opener, &selection.2.value)?.spawn();
+                            if args.stay_open {
+                                let timeout = selection.2.timeout_secs.map(Duration::from_secs);
+                                scrollback.push(selection.1.clone(), spawned, timeout);
+                            } else {
+                                spawned?;
+                                save_last_menu(args, config, &state);
+                                return Ok((String::default(), None));
+                            }
+                        } else if args.stay_open {
+                            let spawned = match exec::command(args, &selection.2.value)? {
+                                Some(mut command) => command.spawn(),
+                                None => unreachable!("--stay-open requires an executor"),
+                            };
+                            let timeout = selection.2.timeout_secs.map(Duration::from_secs);
+                            scrollback.push(selection.1.clone(), spawned, timeout);
+                        } else {
+                            save_last_menu(args, config, &state);
+                            return Ok((selection.2.value.clone(), state.custom_action));
+                        }
                     }
                 }
             }
 
             state.action = Action::Clear;
-            first = false;
-            draw(tty, config, &mut state, menu, &entries)?;
+            let draw_start = Instant::now();
+            draw(tty, args, config, &mut state, menu, &entries, &scrollback)?;
+            if !config.options.title.is_empty() {
+                let title = config.options.title.replace("{menu}", &menu.0);
+                if title != last_title {
+                    queue!(tty, SetTitle(&title))?;
+                    last_title = title;
+                }
+            }
             tty.flush()?;
+            if first {
+                profile::phase("first draw", draw_start);
+                profile::ready();
+            }
+            first = false;
+        }
+        if let Some(key_start) = key_start {
+            profile::key(key_start);
         }
     }
-    Ok(String::default())
+    save_last_menu(args, config, &state);
+    Ok((String::default(), None))
 }
 
-/// Writes the selected entry's value to stdout, or if `--exec` / `--exec-with` is provided,
-/// executes it.
-// TODO clean this up
-fn submit(tty: &mut impl io::Write, args: &Args, selection: String) -> Result<()> {
-    execute!(tty, Clear(ClearType::All), MoveTo(0, 0))?;
-    if args.exec {
-        // --exec
-        Command::new("nohup")
-            .arg(selection)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()?;
-    } else if let Some(e) = &args.exec_with {
-        // --exec-with
-        let mut split = e.split(" ");
-        let cmd = split.next().ok_or_else(|| anyhow!("empty exec_with"))?;
-        let executor_args: Vec<&str> = split.collect();
-        Command::new(cmd)
-            .args(executor_args)
-            .arg(selection)
-            .stdin(Stdio::null())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()?;
+/// Returns the index of the menu with the provided name, for `--menu`.
+fn menu_position(config: &config::Config, name: &str) -> Result<usize> {
+    config
+        .menus
+        .iter()
+        .position(|menu| menu.0 == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = config.menus.iter().map(|menu| menu.0.as_str()).collect();
+            anyhow!(
+                "Menu '{name}' doesn't exist; the menus are: {}",
+                names.join(", ")
+            )
+        })
+}
+
+/// Returns the index of the menu that was active when the program last exited, if the
+/// `remember_menu` option is set, and it's still one of the tabs.
+fn last_menu(config: &config::Config, menu_count: usize) -> Option<usize> {
+    if !config.options.remember_menu {
+        return None;
+    }
+    let name = history::last_menu()?;
+    config.menus[..menu_count]
+        .iter()
+        .position(|menu| menu.0 == name)
+}
+
+/// Saves the tab of the current menu as the last active menu, if the `remember_menu` option is
+/// set.
+fn save_last_menu(args: &Args, config: &config::Config, state: &State) {
+    if !config.options.remember_menu || args.session_chooser {
+        return;
+    }
+    if let Some(menu) = config.menus.get(state.tab_index()) {
+        // Like the history, this only affects convenience, so failing to save it isn't an error
+        let _ = history::save_last_menu(&menu.0);
+    }
+}
+
+/// Describes what a key press does in the current state, without doing it.
+fn describe_key(config: &config::Config, state: &State, event: KeyEvent) -> Result<String> {
+    let entry_key = match hotkey(config, state, event)? {
+        Some(entry_index) => Some(entry_index),
+        None => quick_select(config, state, event)?,
+    };
+    if let Some(entry_index) = entry_key {
+        let menu = &config
+            .menus
+            .get(state.menu_index)
+            .ok_or_else(|| anyhow!("invalid menu index"))?
+            .1;
+        let entries = util::match_entries(state, menu, &config.options);
+        let name = entries
+            .iter()
+            .filter(|entry| util::is_selectable(state, entry))
+            .nth(entry_index)
+            .map(|entry| entry.1.as_str())
+            .unwrap_or_default();
+        let key = keybinds::Keybind::from_event(event);
+        return Ok(format!("{key}: submits {name}"));
+    }
+    if let Some(name) = custom_action(config, event) {
+        let key = keybinds::Keybind::from_event(event);
+        return Ok(format!("{key}: submits with the '{name}' action"));
+    }
+    Ok(config.keybinds.describe(event))
+}
+
+/// Returns the index of the visible entry submitted by the pressed quick select key, with the
+/// `quick_select` option.
+fn quick_select(config: &config::Config, state: &State, event: KeyEvent) -> Result<Option<usize>> {
+    let key = match event.code {
+        KeyCode::Char(c) if config.options.quick_select && event.modifiers == KeyModifiers::ALT => {
+            c
+        }
+        _ => return Ok(None),
+    };
+    let Some(n) = config
+        .keybinds
+        .quick_select_keys()
+        .iter()
+        .position(|&c| c == key)
+    else {
+        return Ok(None);
+    };
+    let menu = &config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?
+        .1;
+    let entries = util::match_entries(state, menu, &config.options);
+    let layout = layout::layout(state, menu, config, &entries)?;
+    if !layout.columns.quick_select {
+        return Ok(None);
+    }
+    Ok(layout.quick_select_entry(state, &entries, n))
+}
+
+/// Returns the index of the entry bound to the pressed key, if the current menu uses hotkeys.
+fn hotkey(config: &config::Config, state: &State, event: KeyEvent) -> Result<Option<usize>> {
+    let menu = &config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?
+        .1;
+    let key = match event.code {
+        KeyCode::Char(c) if menu.hotkeys && (event.modifiers - KeyModifiers::SHIFT).is_empty() => c,
+        _ => return Ok(None),
+    };
+    let entries = util::match_entries(state, menu, &config.options);
+    Ok(util::hotkey_entry(state, &entries, key))
+}
+
+/// Returns the index of the menu opened by the selected entry, if it's a submenu entry.
+fn submenu(config: &config::Config, state: &State) -> Result<Option<usize>> {
+    if state.entry_count == 0 {
+        return Ok(None);
+    }
+    let menu = &config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?
+        .1;
+    let entries = util::match_entries(state, menu, &config.options);
+    let selection = match util::selected_entry(state, &entries) {
+        Some(selection) if selection.2.kind == EntryKind::Submenu => selection,
+        _ => return Ok(None),
+    };
+    match config
+        .menus
+        .iter()
+        .position(|menu| menu.0 == selection.2.value)
+    {
+        Some(index) => Ok(Some(index)),
+        None => bail!("Submenu '{}' doesn't exist", selection.2.value),
+    }
+}
+
+/// Applies the options given as command line arguments, which take precedence over the config.
+fn override_options(args: &Args, config: &mut config::Config) {
+    if let Some(case) = args.case {
+        config.options.case = case;
+    }
+    if args.demo {
+        demo(config);
+    }
+}
+
+/// Hides what shouldn't be shared in a screen recording, for `--demo`: entry values, the names
+/// and descriptions of private entries, and the recently submitted entries. The keybinds that
+/// reveal values or write to the config are removed.
+fn demo(config: &mut config::Config) {
+    /// Shown in place of the names of private entries.
+    const MASK: &str = "••••••";
+
+    let options = &mut config.options;
+    options.show_values = false;
+    options.value_preview = false;
+    options.preview_pane = options::PreviewPane::Off;
+    options.recent_menu = 0;
+    options.frecency = false;
+    for (_, menu) in config.menus.iter_mut() {
+        menu.show_values = None;
+        for (name, entry) in menu.entries.iter_mut().filter(|(_, entry)| entry.private) {
+            *name = MASK.to_string();
+            entry.desc = None;
+        }
+    }
+    config.keybinds.toggle_values.clear();
+    config.keybinds.promote.clear();
+    config.keybinds.disable.clear();
+    config.keybinds.forget.clear();
+}
+
+/// Replaces the config with a freshly loaded one, staying on the current menu if it still exists,
+/// and on the selected entry if it's still in the menu. The config is left alone if it fails to
+/// load, e.g. while a file is half-written. Returns `true` if the config was replaced.
+fn reload(args: &Args, config: &mut config::Config, state: &mut State, path: &Path) -> bool {
+    let mut new_config = match config::load_config(Some(path.to_path_buf()), args.theme.as_deref())
+    {
+        Ok(new_config) if !new_config.menus.is_empty() => new_config,
+        _ => return false,
+    };
+    override_options(args, &mut new_config);
+    theme::set_fallbacks(&new_config.options.attr_fallbacks);
+    util::sort_menus(&mut new_config);
+    util::assign_frecency(&mut new_config);
+    util::insert_recent_menu(&mut new_config);
+
+    let current = config.menus.get(state.menu_index);
+    let selected = current
+        .filter(|_| state.entry_cursor)
+        .and_then(|(_, menu)| {
+            let entries = util::match_entries(state, menu, &config.options);
+            util::selected_entry(state, &entries).map(|entry| entry.1.clone())
+        });
+    state.menu_count = util::count_tabs(&new_config);
+    state.menu_index = new_config
+        .menus
+        .iter()
+        .position(|menu| Some(&menu.0) == current.map(|menu| &menu.0))
+        .filter(|&index| index < state.menu_count)
+        .unwrap_or_default();
+    state.menu_stack.clear();
+    let entry_index = selected.and_then(|selected| {
+        let (_, menu) = new_config.menus.get(state.menu_index)?;
+        util::match_entries(state, menu, &new_config.options)
+            .iter()
+            .filter(|entry| util::is_selectable(state, entry))
+            .position(|entry| entry.1 == selected)
+    });
+    state.entry_cursor = entry_index.is_some();
+    state.entry_index = entry_index.unwrap_or_default();
+    *config = new_config;
+    true
+}
+
+/// Adds the selected entry to the promote menu (the current menu by default), both in the config
+/// file and in the loaded config. If no entries match the input, the input is added instead.
+fn promote(args: &Args, config: &mut config::Config, state: &State) -> Result<()> {
+    let (current, menu) = config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?;
+    let entries = util::match_entries(state, menu, &config.options);
+    let (name, value) = if state.entry_count > 0 {
+        let selection = util::selected_entry(state, &entries)
+            .ok_or_else(|| anyhow!("selection index out of bounds"))?;
+        if selection.2.info || selection.2.kind == EntryKind::Submenu {
+            return Ok(());
+        }
+        (selection.1.clone(), selection.2.value.clone())
+    } else if !state.input.is_empty() {
+        (state.input.clone(), state.input.clone())
     } else {
-        execute!(stdout(), Print(&selection), Print('\n'))?;
+        return Ok(());
+    };
+
+    let target = match &config.options.promote_menu {
+        Some(target) => target.clone(),
+        None => current.clone(),
+    };
+    let menu = match config.menus.iter_mut().find(|(name, _)| *name == target) {
+        Some((_, menu)) => menu,
+        None => bail!("Can't promote '{name}', because menu '{target}' doesn't exist"),
+    };
+    let path = config::config_path(args.config.clone())?;
+    promote::promote(&path, &target, &name, &value)
+        .with_context(|| format!("Failed to promote '{name}' to menu '{target}'"))?;
+
+    if !menu.entries.iter().any(|entry| entry.0 == name) {
+        menu.entries.push((name, config::Entry::from(value)));
+    }
+    Ok(())
+}
+
+/// Disables the selected entry, both in the config file and in the loaded config. Entries in the
+/// recent menu are disabled in the menu they were copied from.
+fn disable(args: &Args, config: &mut config::Config, state: &State) -> Result<()> {
+    if state.entry_count == 0 {
+        return Ok(());
+    }
+    let (current, menu) = config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?;
+    let entries = util::match_entries(state, menu, &config.options);
+    let selection = util::selected_entry(state, &entries)
+        .ok_or_else(|| anyhow!("selection index out of bounds"))?;
+    if selection.2.generated {
+        // Generated entries aren't in the config file
         return Ok(());
     }
+    let name = selection.1.clone();
+    let target = selection
+        .2
+        .origin
+        .clone()
+        .unwrap_or_else(|| current.clone());
 
+    let path = config::config_path(args.config.clone())?;
+    promote::disable(&path, &target, &name)
+        .with_context(|| format!("Failed to disable '{name}' in menu '{target}'"))?;
+
+    for (menu_name, menu) in config.menus.iter_mut() {
+        if *menu_name == target || menu_name == util::RECENT_MENU {
+            menu.entries.retain(|entry| entry.0 != name);
+        }
+    }
+    Ok(())
+}
+
+/// Copies the value of the selected entry to the clipboard, and shows the outcome under the
+/// prompt.
+fn yank(tty: &mut impl io::Write, config: &config::Config, state: &mut State) -> Result<()> {
+    if state.entry_count == 0 {
+        return Ok(());
+    }
+    let (name, value) = {
+        let menu = &config
+            .menus
+            .get(state.menu_index)
+            .ok_or_else(|| anyhow!("invalid menu index"))?
+            .1;
+        let entries = util::match_entries(state, menu, &config.options);
+        let selection = util::selected_entry(state, &entries)
+            .ok_or_else(|| anyhow!("selection index out of bounds"))?;
+        (selection.1.clone(), selection.2.value.clone())
+    };
+    state.key_description = Some(match clipboard::copy(tty, &value) {
+        Ok(()) => format!("Copied the value of '{name}'"),
+        Err(e) => format!("{e:#}"),
+    });
     Ok(())
 }
+
+/// Replaces the input with the name of the selected entry, or of the best match if none is
+/// selected, so that it can be refined or edited.
+fn complete(config: &config::Config, state: &mut State) -> Result<()> {
+    if state.entry_count == 0 {
+        return Ok(());
+    }
+    let menu = &config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?
+        .1;
+    let entries = util::match_entries(state, menu, &config.options);
+    let name = util::selected_entry(state, &entries)
+        .ok_or_else(|| anyhow!("selection index out of bounds"))?
+        .1
+        .clone();
+    state.replace_input(&name);
+    Ok(())
+}
+
+/// Removes the selected entry of the recent menu from the history, or every submission of it if
+/// the `forget_all` option is set, then lists the recent entries and ranks the entries again.
+fn forget(config: &mut config::Config, state: &mut State) -> Result<()> {
+    let (current, menu) = config
+        .menus
+        .get(state.menu_index)
+        .ok_or_else(|| anyhow!("invalid menu index"))?;
+    if current != util::RECENT_MENU || state.entry_count == 0 {
+        return Ok(());
+    }
+    let entries = util::match_entries(state, menu, &config.options);
+    let selection = util::selected_entry(state, &entries)
+        .ok_or_else(|| anyhow!("selection index out of bounds"))?;
+    // Only the entries of the recent menu know their menu; a menu of the config may have its name
+    let Some(origin) = selection.2.origin.clone() else {
+        return Ok(());
+    };
+    let name = selection.1.clone();
+    history::forget(&origin, &name, config.options.forget_all)
+        .with_context(|| format!("Failed to remove '{name}' from the history"))?;
+
+    config.menus.remove(state.menu_index);
+    util::assign_frecency(config);
+    util::insert_recent_menu(config);
+    state.menu_count = util::count_tabs(config);
+    state.entry_cursor = false;
+    state.entry_index = 0;
+    Ok(())
+}
+
+/// Writes the selected entry's value to stdout, `--output-file`, or `--output-socket`, or if
+/// `--exec` / `--exec-with` is provided, executes it. If it was submitted with a custom action,
+/// the action's `exec_with` executes it instead, or else its name is written before the value.
+fn submit(
+    tty: &mut impl io::Write,
+    args: &Args,
+    selection: String,
+    action: Option<(config::CustomAction, String)>,
+) -> Result<()> {
+    execute!(tty, Clear(ClearType::All), MoveTo(0, 0))?;
+    let command = match action
+        .as_ref()
+        .and_then(|(action, _)| action.exec_with.as_ref())
+    {
+        Some(exec_with) => Some(exec::execute_with(exec_with, &selection)?),
+        None => exec::command(args, &selection)?,
+    };
+    match (command, action) {
+        (Some(mut command), _) => {
+            command.spawn()?;
+        }
+        (None, Some((_, name))) => {
+            output::Sink::from_args(args).write(&format!("{name}\t{selection}"))?
+        }
+        (None, None) => output::Sink::from_args(args).write(&selection)?,
+    }
+    Ok(())
+}
+
+/// Returns the name of the custom action bound to the pressed key, if any.
+fn custom_action(config: &config::Config, event: KeyEvent) -> Option<String> {
+    config
+        .actions
+        .iter()
+        .find(|(_, action)| action.keybind.matches(event))
+        .map(|(name, _)| name.clone())
+}
@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Parses the simple Pango-like markup accepted in entries and the message with `--markup`, as
+//! in rofi: `<b>`, `<i>`, `<u>`, and `<span>` tags with `foreground`, `weight`, `style`, and
+//! `underline` attributes, and the `&amp;`, `&lt;`, `&gt;`, `&quot;`, and `&apos;` entities.
+//!
+//! Unknown tags are left out of the text, and text that isn't valid markup is kept as it is.
+
+use std::ops::Range;
+
+use crossterm::style::{Attribute, Attributes, Color};
+
+use crate::theme;
+
+/// A run of characters drawn with extra attributes or a different color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Span {
+    /// The indices of the characters in the plain text.
+    pub(crate) chars: Range<usize>,
+
+    /// Attributes added to the style the characters would otherwise have.
+    pub(crate) attrs: Attributes,
+
+    /// Replaces the foreground color the characters would otherwise have.
+    pub(crate) fg: Option<Color>,
+}
+
+/// The formatting of an open tag.
+#[derive(Default, Clone, Copy)]
+struct Format {
+    attrs: Attributes,
+    fg: Option<Color>,
+}
+
+/// Splits markup into its plain text and the spans that are formatted.
+pub(crate) fn parse(text: &str) -> (String, Vec<Span>) {
+    let mut plain = String::with_capacity(text.len());
+    let mut spans: Vec<Span> = Vec::new();
+    let mut stack: Vec<Format> = Vec::new();
+    let mut count = 0;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let tag = rest
+            .strip_prefix('<')
+            .and_then(|tag| tag.split_once('>'))
+            .filter(|(tag, _)| !tag.contains('<'));
+        let escaped = rest
+            .strip_prefix('&')
+            .and_then(|s| s.split_once(';'))
+            .and_then(|(name, after)| Some((entity(name)?, after)));
+
+        if let Some((tag, after)) = tag {
+            rest = after;
+            if tag.starts_with('/') {
+                stack.pop();
+                continue;
+            }
+            let mut format = stack.last().copied().unwrap_or_default();
+            apply(&mut format, tag);
+            // Self-closing tags don't format anything
+            if !tag.ends_with('/') {
+                stack.push(format);
+            }
+            continue;
+        }
+
+        let (c, after) = match escaped {
+            Some((c, after)) => (c, after),
+            None => (c, &rest[c.len_utf8()..]),
+        };
+        rest = after;
+        plain.push(c);
+        if let Some(format) = stack
+            .last()
+            .filter(|f| !f.attrs.is_empty() || f.fg.is_some())
+        {
+            // Extend the previous span if the formatting continues
+            match spans.last_mut() {
+                Some(span)
+                    if span.chars.end == count
+                        && span.attrs == format.attrs
+                        && span.fg == format.fg =>
+                {
+                    span.chars.end += 1;
+                }
+                _ => spans.push(Span {
+                    chars: count..count + 1,
+                    attrs: format.attrs,
+                    fg: format.fg,
+                }),
+            }
+        }
+        count += 1;
+    }
+
+    (plain, spans)
+}
+
+/// Returns the formatting of the span at the character `index`, if any.
+pub(crate) fn at(spans: &[Span], index: usize) -> Option<&Span> {
+    spans.iter().find(|span| span.chars.contains(&index))
+}
+
+/// Adds the formatting of an opening tag, e.g. `span foreground="red"`.
+fn apply(format: &mut Format, tag: &str) {
+    let tag = tag.trim_end_matches('/');
+    let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+    match name {
+        "b" => format.attrs.set(Attribute::Bold),
+        "i" => format.attrs.set(Attribute::Italic),
+        "u" => format.attrs.set(Attribute::Underlined),
+        "span" => {
+            for (key, value) in attributes_of(attributes) {
+                match (key, value) {
+                    ("foreground" | "fgcolor" | "color", color) => {
+                        if let Some(color) = theme::parse_color(color) {
+                            format.fg = Some(color);
+                        }
+                    }
+                    ("weight", "bold" | "ultrabold" | "heavy") => {
+                        format.attrs.set(Attribute::Bold);
+                    }
+                    ("style", "italic" | "oblique") => format.attrs.set(Attribute::Italic),
+                    ("underline", "single" | "double" | "low") => {
+                        format.attrs.set(Attribute::Underlined);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parses the `key="value"` attributes of a tag.
+fn attributes_of(mut s: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    while let Some((key, after)) = s.split_once('=') {
+        let after = after.trim_start();
+        let Some(quote) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some((value, after)) = after[1..].split_once(quote) else {
+            break;
+        };
+        attributes.push((key.trim(), value));
+        s = after;
+    }
+    attributes
+}
+
+/// Returns the character an entity stands for, e.g. `<` for `lt`.
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => None,
+    }
+}
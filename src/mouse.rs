@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Handles mouse clicks and scrolling, with the `mouse` option.
+
+use anyhow::{anyhow, Result};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+
+use crate::{
+    config::Config,
+    layout,
+    state::{Action, State},
+    util,
+};
+
+/// Handles a mouse event. Clicking an entry selects and submits it, clicking a menu tab switches
+/// to it, clicking a crumb in the breadcrumb trail returns to that submenu, and the wheel moves
+/// the entry cursor while the mouse is over the entries.
+pub(crate) fn handle(config: &Config, mut state: State, event: MouseEvent) -> Result<State> {
+    let Some((column, row)) = state.frame.relative(event.column, event.row) else {
+        return Ok(state);
+    };
+    let rows = config.layout.rows();
+    let entry_row = usize::from(row)
+        .checked_sub(usize::from(rows.entries))
+        .filter(|row| *row < state.entry_rows);
+
+    match event.kind {
+        MouseEventKind::Down(MouseButton::Left) if rows.menu_line == Some(row) => {
+            let tabs = layout::tabs(&config.menus, &config.layout, &state)?;
+            let tab = tabs.ranges.iter().position(|tab| tab.contains(&column));
+            let crumb = tabs
+                .breadcrumbs
+                .iter()
+                .position(|crumb| crumb.1.contains(&column));
+            if let Some(tab) = tab {
+                state.switch_tab(tab);
+            } else if let Some(crumb) = crumb {
+                // The first crumb is the submenu opened from the tab
+                state.leave_to(crumb + 1);
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            let Some(row) = entry_row else {
+                return Ok(state);
+            };
+            let menu = &config
+                .menus
+                .get(state.menu_index)
+                .ok_or_else(|| anyhow!("invalid menu index"))?
+                .1;
+            let entries = util::match_entries(&state, menu, &config.options);
+            let layout = layout::layout(&state, menu, config, &entries)?;
+            if let Some(entry_index) = layout.entry_at(&state, &entries, row, column) {
+                state.entry_cursor = true;
+                state.entry_index = entry_index;
+                state.action = Action::Submit;
+            }
+        }
+        MouseEventKind::ScrollDown if entry_row.is_some() && state.entry_count > 0 => {
+            state.entry_index = if state.entry_cursor {
+                (state.entry_index + 1).min(state.entry_count - 1)
+            } else {
+                0
+            };
+            state.entry_cursor = true;
+        }
+        MouseEventKind::ScrollUp if entry_row.is_some() && state.entry_cursor => {
+            state.entry_index = state.entry_index.saturating_sub(1);
+        }
+        _ => {}
+    }
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+
+    use super::*;
+    use crate::{
+        config::{self, Menu},
+        frame::Frame,
+    };
+
+    /// Returns a left click at a column of the menu line.
+    fn click(config: &Config, column: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row: config.layout.rows().menu_line.unwrap(),
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn clicking_the_breadcrumb_trail_returns_to_a_submenu() {
+        let mut config = config::load_config(None, None).unwrap();
+        config.menus = ["apps", "power", "editors", "vim", "plugins"]
+            .map(|name| (name.to_string(), Menu::default()))
+            .into();
+        let mut state = State {
+            menu_count: 2,
+            frame: Frame::sized(80, 24),
+            ..Default::default()
+        };
+        for submenu in [2, 3, 4] {
+            state.enter_submenu(submenu);
+        }
+        let tabs = layout::tabs(&config.menus, &config.layout, &state).unwrap();
+        let crumbs: Vec<&str> = tabs.breadcrumbs.iter().map(|crumb| &crumb.0[..]).collect();
+        assert_eq!(crumbs, ["▸ editors ", "▸ vim ", "▸ plugins "]);
+
+        // Clicking the current submenu does nothing
+        let end = tabs.breadcrumbs[2].1.end - 1;
+        let state = handle(&config, state, click(&config, end)).unwrap();
+        assert_eq!(state.menu_index, 4);
+
+        let editors = tabs.breadcrumbs[0].1.start;
+        let state = handle(&config, state, click(&config, editors)).unwrap();
+        assert_eq!(
+            (state.menu_index, state.menu_stack.as_slice()),
+            (2, &[0][..])
+        );
+
+        let tab = tabs.ranges[0].start;
+        let state = handle(&config, state, click(&config, tab)).unwrap();
+        assert_eq!(
+            (state.menu_index, state.menu_stack.as_slice()),
+            (0, &[][..])
+        );
+    }
+}
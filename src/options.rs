@@ -0,0 +1,282 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Behavior configuration.
+//!
+//! See [Options] to view the accepted fields in an options configuration.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::theme::AttributeFallbacks;
+
+/// How letter case is treated when matching the input.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CaseMode {
+    /// Letters match regardless of case.
+    #[default]
+    Insensitive,
+
+    /// Letters match regardless of case, unless the input contains an uppercase letter.
+    Smart,
+
+    /// Letters only match letters of the same case.
+    Sensitive,
+}
+
+/// How the input is matched against entries.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MatchMode {
+    /// The input's characters appear in order, and entries are sorted by how well they match.
+    #[default]
+    Fuzzy,
+
+    /// The input appears anywhere.
+    Substring,
+
+    /// The input appears at the start.
+    Prefix,
+
+    /// The input is the whole text.
+    Exact,
+}
+
+impl MatchMode {
+    /// Returns the mode that the match mode keybind switches to from this one.
+    pub(crate) fn next(self) -> Self {
+        match self {
+            MatchMode::Fuzzy => MatchMode::Substring,
+            MatchMode::Substring => MatchMode::Prefix,
+            MatchMode::Prefix => MatchMode::Exact,
+            MatchMode::Exact => MatchMode::Fuzzy,
+        }
+    }
+
+    /// Returns the name used for the mode in the config.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            MatchMode::Fuzzy => "fuzzy",
+            MatchMode::Substring => "substring",
+            MatchMode::Prefix => "prefix",
+            MatchMode::Exact => "exact",
+        }
+    }
+}
+
+/// The algorithm used for fuzzy matching.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Algorithm {
+    /// The algorithm used by clangd for code completion.
+    #[default]
+    Clangd,
+
+    /// The algorithm used by skim.
+    Skim,
+
+    /// The algorithm used by nucleo, which is similar to fzf's.
+    Nucleo,
+}
+
+/// How menus are sorted.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum MenuOrder {
+    /// By each menu's `order`, then by name.
+    #[default]
+    Order,
+
+    /// By how often entries are submitted from each menu, most often first. Ties are sorted as
+    /// with [MenuOrder::Order].
+    Usage,
+}
+
+/// Where entry values are placed.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ValueAlign {
+    /// Against the right edge of the terminal.
+    #[default]
+    Right,
+
+    /// In a column after the names and descriptions.
+    Left,
+}
+
+/// Where the preview pane is shown.
+#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PreviewPane {
+    /// The preview pane isn't shown.
+    #[default]
+    Off,
+
+    /// To the right of the entries.
+    Right,
+
+    /// Below the entries.
+    Bottom,
+}
+
+/// The characters that the box around the interface is bordered with, in the order `─│┌┐└┘`.
+/// There's no border if `None`.
+#[derive(Debug, Default, Clone, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "String")]
+pub(crate) struct BoxBorder(pub(crate) Option<[char; 6]>);
+
+impl TryFrom<String> for BoxBorder {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let chars = match s.as_str() {
+            "none" => return Ok(BoxBorder(None)),
+            "sharp" => "─│┌┐└┘",
+            "rounded" => "─│╭╮╰╯",
+            "double" => "═║╔╗╚╝",
+            "thick" => "━┃┏┓┗┛",
+            chars => chars,
+        };
+        let chars: Vec<char> = chars.chars().collect();
+        chars
+            .try_into()
+            .map(|chars| BoxBorder(Some(chars)))
+            .map_err(|_| {
+                format!(
+                    "invalid border '{s}', expected none, sharp, rounded, double, thick, or six \
+                 border characters"
+                )
+            })
+    }
+}
+
+/// Settings that change how the interface behaves.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Options {
+    /// Indicates that the exit keybinds back out one level at a time before exiting. See
+    /// [State::back_out](crate::state::State::back_out).
+    pub(crate) exit_backs_out: bool,
+
+    /// Pressing an exit keybind twice within this many milliseconds exits immediately, without
+    /// backing out.
+    pub(crate) double_exit_ms: u64,
+
+    /// The menu that the promote keybind adds entries to. Defaults to the current menu.
+    #[serde(default)]
+    pub(crate) promote_menu: Option<String>,
+
+    /// How menus are sorted.
+    pub(crate) menu_order: MenuOrder,
+
+    /// Indicates that the input is matched against entry values as well as names.
+    pub(crate) match_values: bool,
+
+    /// How letter case is treated when matching the input.
+    pub(crate) case: CaseMode,
+
+    /// How the input is matched against entries.
+    pub(crate) match_mode: MatchMode,
+
+    /// The algorithm used for fuzzy matching.
+    pub(crate) algorithm: Algorithm,
+
+    /// Indicates that entry values are shown on the right side of the entries.
+    pub(crate) show_values: bool,
+
+    /// Where entry values are placed.
+    pub(crate) value_align: ValueAlign,
+
+    /// Values longer than this are cut off with an overflow indicator. Disabled if zero.
+    pub(crate) value_max_width: usize,
+
+    /// Indicates that the background of the selected entry's style fills its whole row, when
+    /// there's one entry per row.
+    pub(crate) full_line_cursor: bool,
+
+    /// Indicates that the full value of the selected entry is shown under the prompt.
+    pub(crate) value_preview: bool,
+
+    /// Where the preview pane for the selected entry is shown.
+    pub(crate) preview_pane: PreviewPane,
+
+    /// The percentage of the terminal's width or height taken by the preview pane.
+    pub(crate) preview_size: u16,
+
+    /// The command whose output is shown in the preview pane, with `{}` replaced by the value of
+    /// the selected entry. The entry's details are shown instead if it's empty.
+    pub(crate) preview_command: String,
+
+    /// Fuzzy matches scoring below this are treated as not matching. Disabled if zero.
+    pub(crate) min_score: i64,
+
+    /// Indicates that entries that don't match the input are still shown, after the matches.
+    pub(crate) show_hidden: bool,
+
+    /// Indicates that the menu that was active when the program last exited is opened at start,
+    /// instead of the first one.
+    pub(crate) remember_menu: bool,
+
+    /// The number of recently submitted entries shown in the recent menu, which is the first tab.
+    /// The menu is left out if zero.
+    pub(crate) recent_menu: usize,
+
+    /// Indicates that escape enters a normal mode, where keys navigate like in vim instead of
+    /// typing.
+    pub(crate) vim_mode: bool,
+
+    /// Indicates that the forget keybind removes every submission of the selected entry from the
+    /// history, instead of only the most recent one.
+    pub(crate) forget_all: bool,
+
+    /// Indicates that entries are ranked higher the more often and recently they were submitted.
+    pub(crate) frecency: bool,
+
+    /// Indicates that the input is also matched against entry names transliterated into Latin
+    /// letters. Requires the `transliteration` feature.
+    pub(crate) transliterate: bool,
+
+    /// The number of entries shown side by side in each row. As many as fit are shown if zero.
+    pub(crate) columns: usize,
+
+    /// Indicates that the config may include files from the URLs listed in `include`. Requires
+    /// the `remote` feature.
+    pub(crate) remote_includes: bool,
+
+    /// Indicates that entries and menu tabs can be clicked, and the entries scrolled with the
+    /// mouse wheel.
+    pub(crate) mouse: bool,
+
+    /// Text attributes that are drawn as other attributes, or not at all.
+    pub(crate) attr_fallbacks: AttributeFallbacks,
+
+    /// The width of the box that the interface is drawn in, centered in the terminal. The
+    /// interface takes the terminal's whole width if zero.
+    pub(crate) box_width: u16,
+
+    /// The height of the box that the interface is drawn in. The interface takes the terminal's
+    /// whole height if zero.
+    pub(crate) box_height: u16,
+
+    /// The characters that the box is bordered with.
+    pub(crate) box_border: BoxBorder,
+
+    /// The number of cells between the border of the box and the interface.
+    pub(crate) box_padding: u16,
+
+    /// The terminal's window title while the menu is open, with `{menu}` replaced by the name of
+    /// the current menu. The title is left alone if it's empty.
+    pub(crate) title: String,
+
+    /// Indicates that the number of matching entries, out of all the entries, is shown after the
+    /// input.
+    pub(crate) match_count: bool,
+
+    /// The command that link entries are opened with, followed by the URL.
+    pub(crate) opener: String,
+
+    /// The icon shown before link entries that don't have their own. Not shown if it's empty.
+    pub(crate) link_icon: String,
+
+    /// Indicates that the visible entries are numbered 1-9, then a-z, and submitted by pressing
+    /// alt with their number. Keys bound with alt to a command are skipped.
+    pub(crate) quick_select: bool,
+}
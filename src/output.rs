@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Writes selections to stdout, files, sockets, or named pipes.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    os::unix::{fs::FileTypeExt, net::UnixStream},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{args::Args, util};
+
+/// Where a selection is written.
+pub(crate) enum Sink<'a> {
+    Stdout,
+
+    /// A file that's replaced, or appended to if `append` is set.
+    File {
+        path: &'a Path,
+        append: bool,
+    },
+
+    /// A Unix socket or a named pipe, which a long-running consumer reads selections from.
+    Socket(&'a Path),
+}
+
+impl<'a> Sink<'a> {
+    /// Returns the sink chosen with `--output-file` or `--output-socket`, or else stdout.
+    pub(crate) fn from_args(args: &'a Args) -> Self {
+        if let Some(path) = &args.output_file {
+            Sink::File {
+                path,
+                append: args.append,
+            }
+        } else if let Some(path) = &args.output_socket {
+            Sink::Socket(path)
+        } else {
+            Sink::Stdout
+        }
+    }
+
+    /// Writes a selection to the sink followed by a newline. Nothing is written to files and
+    /// sockets if the selection is empty, i.e. nothing was selected.
+    pub(crate) fn write(&self, selection: &str) -> Result<()> {
+        match self {
+            Sink::Stdout => {
+                writeln!(io::stdout(), "{selection}").context("Failed to write to stdout")
+            }
+            _ if selection.is_empty() => Ok(()),
+            Sink::File { path, append } => write_file(path, selection, *append),
+            Sink::Socket(path) => write_socket(path, selection),
+        }
+    }
+}
+
+/// Writes a selection to `path` followed by a newline. The file is replaced atomically, so that a
+/// reader never sees it partially written. If `append` is set, the selection is appended instead.
+fn write_file(path: &Path, selection: &str, append: bool) -> Result<()> {
+    if append {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open output file {}", path.display()))?;
+        // Other instances may be appending at the same time
+        file.lock()
+            .with_context(|| format!("Failed to lock output file {}", path.display()))?;
+        file.write_all(format!("{selection}\n").as_bytes())
+            .with_context(|| format!("Failed to append to output file {}", path.display()))?;
+        return Ok(());
+    }
+
+    util::write_atomic(path, format!("{selection}\n"))
+        .with_context(|| format!("Failed to replace output file {}", path.display()))
+}
+
+/// Writes a selection followed by a newline to the Unix socket or named pipe at `path`. Writing
+/// to a named pipe waits until it's opened for reading.
+fn write_socket(path: &Path, selection: &str) -> Result<()> {
+    let file_type = fs::metadata(path)
+        .with_context(|| format!("Failed to find output socket {}", path.display()))?
+        .file_type();
+    let line = format!("{selection}\n");
+    if file_type.is_socket() {
+        UnixStream::connect(path)
+            .and_then(|mut stream| stream.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write to output socket {}", path.display()))
+    } else if file_type.is_fifo() {
+        OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|mut fifo| fifo.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write to named pipe {}", path.display()))
+    } else {
+        bail!("{} isn't a socket or a named pipe", path.display())
+    }
+}
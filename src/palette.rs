@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Imports the colors of a Base16 scheme or of pywal into a theme, so that the interface matches
+//! the rest of a themed desktop.
+//!
+//! The colors are given roles, such as the background or the color of errors, which are filled
+//! into a copy of the default theme with its colors replaced by those roles.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// The default theme, with each color replaced by the name of its role in braces, e.g. `{fg}`.
+static PALETTE_THEME: &str = include_str!("../config/theme.palette.toml");
+
+/// The keys of a Base16 scheme that fill each role.
+const BASE16_ROLES: &[(&str, &str)] = &[
+    ("bg", "base00"),
+    ("surface", "base02"),
+    ("dim", "base03"),
+    ("subtle", "base04"),
+    ("fg", "base05"),
+    ("red", "base08"),
+    ("orange", "base09"),
+    ("yellow", "base0a"),
+    ("green", "base0b"),
+    ("cyan", "base0c"),
+    ("blue", "base0d"),
+    ("magenta", "base0e"),
+];
+
+/// The keys of pywal's `colors.json` that fill each role, within its `special` and `colors`
+/// tables. pywal has no orange, so bright yellow stands in for it.
+const PYWAL_ROLES: &[(&str, &str, &str)] = &[
+    ("bg", "special", "background"),
+    ("surface", "colors", "color8"),
+    ("dim", "colors", "color8"),
+    ("subtle", "colors", "color7"),
+    ("fg", "special", "foreground"),
+    ("red", "colors", "color1"),
+    ("orange", "colors", "color11"),
+    ("yellow", "colors", "color3"),
+    ("green", "colors", "color2"),
+    ("cyan", "colors", "color6"),
+    ("blue", "colors", "color4"),
+    ("magenta", "colors", "color5"),
+];
+
+type Table = HashMap<String, config::Value>;
+
+/// Reads a Base16 scheme (YAML, with its colors at the top level or in a `palette` table) or
+/// pywal's `colors.json`, and returns a config with a `[theme]` table in its colors.
+pub(crate) fn theme(path: &Path) -> Result<String> {
+    let format = crate::config::config_format(path)?;
+    let root: Table = config::Config::builder()
+        .add_source(config::File::from(path).format(format))
+        .build()
+        .and_then(|scheme| scheme.try_deserialize())
+        .with_context(|| format!("Failed to read the colors in '{}'", path.display()))?;
+
+    let colors: Vec<(&str, String)> = if get(&root, "special").is_some() {
+        PYWAL_ROLES
+            .iter()
+            .map(|(role, table, key)| {
+                let values = get(&root, table).and_then(|values| values.into_table().ok());
+                let color = values.and_then(|values| get(&values, key));
+                Ok((*role, hex(color, &format!("{table}.{key}"))?))
+            })
+            .collect::<Result<_>>()?
+    } else {
+        let palette = match get(&root, "palette") {
+            Some(palette) => palette.into_table()?,
+            None => root,
+        };
+        BASE16_ROLES
+            .iter()
+            .map(|(role, key)| Ok((*role, hex(get(&palette, key), key)?)))
+            .collect::<Result<_>>()?
+    };
+
+    let mut theme = PALETTE_THEME.to_string();
+    for (role, color) in colors {
+        theme = theme.replace(&format!("{{{role}}}"), &color);
+    }
+    Ok(theme)
+}
+
+/// Returns the value of a key, ignoring case, since Base16 schemes differ on the case of e.g.
+/// `base0A`.
+fn get(table: &Table, key: &str) -> Option<config::Value> {
+    table
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value.clone())
+}
+
+/// Parses a hex color, with or without a leading `#` as Base16 schemes write them, into `#rrggbb`.
+fn hex(value: Option<config::Value>, key: &str) -> Result<String> {
+    let value = value
+        .ok_or_else(|| anyhow!("The color '{key}' is missing"))?
+        .into_string()?;
+    let digits = value.trim().trim_start_matches('#');
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("The color '{key}' isn't a hex color: '{value}'");
+    }
+    Ok(format!("#{}", digits.to_lowercase()))
+}
@@ -0,0 +1,217 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Opens the menu in a new terminal window that floats in the center of the screen, like a
+//! graphical launcher.
+//!
+//! The terminal is given a window class so that window rules can match it, and the running
+//! compositor is asked to float, size, and center the window: Hyprland with rules passed along
+//! with the command, and sway and i3 with a command sent once the window has appeared. Under any
+//! other compositor, the terminal is only asked for its size.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::{config, util::quote};
+
+/// How long sway or i3 is given for the window to appear before giving up on floating it.
+const WINDOW_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How often sway or i3 is asked to float the window until it has appeared.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The terminal and window of the popup, set by the `[popup]` section. It's read on its own by
+/// the popup subcommand, so that opening the popup doesn't generate the menus twice, and
+/// validated by `fr33zmenu check`.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct PopupOptions {
+    /// The terminal to open the menu in, which must be one of [TERMINALS]. If it's empty,
+    /// `$TERMINAL` is used if it's one of them, or else the first of them that's installed.
+    pub(crate) terminal: String,
+
+    /// The window class (or app ID on Wayland) of the terminal window.
+    pub(crate) class: String,
+
+    /// The width of the window in pixels.
+    pub(crate) width: u32,
+
+    /// The height of the window in pixels.
+    pub(crate) height: u32,
+}
+
+/// A terminal that the popup can be opened in.
+struct Terminal {
+    /// The name of the terminal's program.
+    program: &'static str,
+
+    /// Builds the arguments that set the window class and size, from the options.
+    window_args: fn(&PopupOptions) -> Vec<String>,
+
+    /// The arguments between the terminal's own arguments and the command it runs.
+    exec_args: &'static [&'static str],
+}
+
+/// The supported terminals, in order of preference when none is configured. Terminals that take
+/// their size in cells rather than pixels are left to the compositor to size.
+const TERMINALS: &[Terminal] = &[
+    Terminal {
+        program: "kitty",
+        window_args: |options| {
+            vec![
+                format!("--class={}", options.class),
+                format!("--override=initial_window_width={}", options.width),
+                format!("--override=initial_window_height={}", options.height),
+                "--override=remember_window_size=no".to_string(),
+            ]
+        },
+        exec_args: &[],
+    },
+    Terminal {
+        program: "foot",
+        window_args: |options| {
+            vec![
+                format!("--app-id={}", options.class),
+                format!("--window-size-pixels={}x{}", options.width, options.height),
+            ]
+        },
+        exec_args: &[],
+    },
+    Terminal {
+        program: "alacritty",
+        window_args: |options| vec!["--class".to_string(), options.class.clone()],
+        exec_args: &["-e"],
+    },
+    Terminal {
+        program: "wezterm",
+        window_args: |options| {
+            vec![
+                "start".to_string(),
+                "--class".to_string(),
+                options.class.clone(),
+            ]
+        },
+        exec_args: &["--"],
+    },
+];
+
+/// Opens the menu in a popup terminal window, with the config at the provided path (see
+/// [config::config_path]) and the rest of the arguments passed along to it. Returns once the
+/// window has been opened, without waiting for the menu to close.
+pub(crate) fn popup(path: Option<PathBuf>, menu_args: &[String]) -> Result<()> {
+    let path = config::config_path(path)?;
+    let options: PopupOptions = config::read_config(Some(path.clone()), None)?
+        .get("popup")
+        .context("Failed to deserialize the popup options")?;
+    let terminal = terminal(&options)?;
+
+    let exe = env::current_exe().context("Failed to find the fr33zmenu executable")?;
+    let mut command = vec![terminal.program.to_string()];
+    command.extend((terminal.window_args)(&options));
+    command.extend(terminal.exec_args.iter().map(|arg| arg.to_string()));
+    command.push(exe.to_string_lossy().into_owned());
+    command.push(path.to_string_lossy().into_owned());
+    command.extend(menu_args.iter().cloned());
+
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        // The rules only apply to the window opened by this command
+        let rules = format!("[float; size {} {}; center]", options.width, options.height);
+        let command: Vec<String> = command.iter().map(|arg| quote(arg)).collect();
+        return run(Command::new("hyprctl")
+            .args(["dispatch", "exec"])
+            .arg(format!("{rules} {}", command.join(" "))));
+    }
+
+    spawn(&command)?;
+    if env::var_os("SWAYSOCK").is_some() {
+        float(
+            "swaymsg",
+            &format!("[app_id=\"{}\"]", options.class),
+            &options,
+        )
+    } else if env::var_os("I3SOCK").is_some() {
+        float(
+            "i3-msg",
+            &format!("[class=\"{}\"]", options.class),
+            &options,
+        )
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the configured terminal, or picks one if none is configured.
+fn terminal(options: &PopupOptions) -> Result<&'static Terminal> {
+    let find = |program: &str| TERMINALS.iter().find(|t| t.program == program);
+    if !options.terminal.is_empty() {
+        let supported: Vec<&str> = TERMINALS.iter().map(|t| t.program).collect();
+        return find(&options.terminal).ok_or_else(|| {
+            anyhow!(
+                "Unsupported popup terminal '{}', expected one of: {}",
+                options.terminal,
+                supported.join(", ")
+            )
+        });
+    }
+    env::var("TERMINAL")
+        .ok()
+        .and_then(|program| find(&program))
+        .or_else(|| TERMINALS.iter().find(|t| installed(t.program)))
+        .ok_or_else(|| anyhow!("No supported terminal is installed for the popup"))
+}
+
+/// Returns whether a program is in one of the directories of `$PATH`.
+fn installed(program: &str) -> bool {
+    let path = env::var_os("PATH").unwrap_or_default();
+    env::split_paths(&path).any(|dir| Path::new(&dir).join(program).is_file())
+}
+
+/// Starts the terminal in the background, so that it outlives this process.
+fn spawn(command: &[String]) -> Result<()> {
+    Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to start the terminal '{}'", command[0]))?;
+    Ok(())
+}
+
+/// Runs a compositor command, failing if it exits unsuccessfully.
+fn run(command: &mut Command) -> Result<()> {
+    let output = command
+        .output()
+        .context("Failed to message the compositor")?;
+    if !output.status.success() {
+        bail!(
+            "The compositor rejected the popup: {}",
+            String::from_utf8_lossy(&output.stdout).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Asks sway or i3 to float, size, and center the window matching the criteria, retrying until
+/// the window has appeared, since these compositors can't apply rules to a window before it
+/// exists without adding them to the config for good.
+fn float(program: &str, criteria: &str, options: &PopupOptions) -> Result<()> {
+    let message = format!(
+        "{criteria} floating enable, resize set {} {}, move position center",
+        options.width, options.height
+    );
+    let start = Instant::now();
+    while start.elapsed() < WINDOW_TIMEOUT {
+        // Both exit unsuccessfully while no window matches
+        if run(Command::new(program).arg(&message)).is_ok() {
+            return Ok(());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    bail!("The popup window didn't appear within {WINDOW_TIMEOUT:?}")
+}
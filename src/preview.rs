@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Runs the preview command for the selected entry in the background, for the preview pane.
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use crate::util;
+
+/// The most lines of output kept from a preview command. The command is stopped once it has
+/// written this many.
+const MAX_LINES: usize = 200;
+
+/// The preview command that's running, or has finished, for the selected entry.
+#[derive(Default)]
+pub(crate) struct Previewer {
+    /// The command line of the latest preview, with the entry value filled in.
+    current: Option<String>,
+
+    /// The command's process, until its output has been received.
+    child: Option<Child>,
+
+    /// Receives the command's output once it has finished writing it.
+    output: Option<Receiver<Vec<String>>>,
+}
+
+impl Previewer {
+    /// Starts the preview command for an entry value, stopping the previous one. Every `{}` in
+    /// the command is replaced with the quoted value. Returns `false` if the command already
+    /// started for the same value.
+    pub(crate) fn request(&mut self, command: &str, value: &str) -> bool {
+        let command = command.replace("{}", &util::quote(value));
+        if self.current.as_ref() == Some(&command) {
+            return false;
+        }
+        self.stop();
+
+        let (sender, receiver) = mpsc::channel();
+        let spawned = Command::new("/bin/sh")
+            .arg("-c")
+            .arg(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                let stdout = child.stdout.take();
+                thread::spawn(move || {
+                    let mut lines = Vec::new();
+                    if let Some(stdout) = stdout {
+                        let mut reader = BufReader::new(stdout);
+                        let mut line = Vec::new();
+                        while lines.len() < MAX_LINES
+                            && reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0)
+                        {
+                            lines.push(sanitize(&String::from_utf8_lossy(&line)));
+                            line.clear();
+                        }
+                    }
+                    // The receiver is gone if another preview was requested in the meantime
+                    let _ = sender.send(lines);
+                });
+                self.child = Some(child);
+            }
+            Err(e) => {
+                let _ = sender.send(vec![format!("Failed to run preview command: {e}")]);
+            }
+        }
+        self.current = Some(command);
+        self.output = Some(receiver);
+        true
+    }
+
+    /// Stops the preview command and forgets its output, e.g. when no entry is selected.
+    pub(crate) fn clear(&mut self) {
+        self.stop();
+        self.current = None;
+    }
+
+    /// Returns the output of the preview command if it has finished writing it since the last
+    /// call. Doesn't block.
+    pub(crate) fn poll(&mut self) -> Option<Vec<String>> {
+        let lines = self.output.as_ref()?.try_recv().ok()?;
+        self.stop();
+        Some(lines)
+    }
+
+    /// Kills the preview command's process if it's still running, and waits for it.
+    fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.output = None;
+    }
+}
+
+impl Drop for Previewer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Removes escape sequences and control characters from a line of output, which would otherwise
+/// move the cursor or change the style. Tabs are expanded to spaces.
+fn sanitize(line: &str) -> String {
+    let mut sanitized = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' => sanitized.push_str("    "),
+            '\x1b' => {
+                // Skip a CSI sequence up to its final byte, or else the one character after ESC
+                if chars.next() == Some('[') {
+                    for c in chars.by_ref() {
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+            }
+            c if c.is_control() => {}
+            c => sanitized.push(c),
+        }
+    }
+    sanitized
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Measures how long startup phases and key presses take, for `--profile`.
+
+use std::{
+    io::{self, Write},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Timings collected while profiling.
+struct Profile {
+    /// When profiling was enabled, i.e. when the program started.
+    start: Instant,
+
+    /// How long each startup phase took, in the order they first happened. Repeated phases are
+    /// added together.
+    phases: Vec<(&'static str, Duration)>,
+
+    /// How long it took from the start until the first frame was drawn. Phases are only recorded
+    /// until then.
+    ready: Option<Duration>,
+
+    /// How long each key press took to handle and draw.
+    keys: Vec<Duration>,
+}
+
+static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+
+/// Runs `f` with the profile, if profiling is enabled.
+fn with_profile(f: impl FnOnce(&mut Profile)) {
+    if let Ok(mut profile) = PROFILE.lock() {
+        if let Some(profile) = profile.as_mut() {
+            f(profile);
+        }
+    }
+}
+
+/// Starts profiling. Nothing is recorded unless this is called.
+pub(crate) fn enable() {
+    if let Ok(mut profile) = PROFILE.lock() {
+        *profile = Some(Profile {
+            start: Instant::now(),
+            phases: Vec::new(),
+            ready: None,
+            keys: Vec::new(),
+        });
+    }
+}
+
+/// Records the time since `start` as part of a startup phase.
+pub(crate) fn phase(name: &'static str, start: Instant) {
+    let elapsed = start.elapsed();
+    with_profile(|profile| {
+        if profile.ready.is_some() {
+            return;
+        }
+        match profile.phases.iter_mut().find(|(phase, _)| *phase == name) {
+            Some((_, duration)) => *duration += elapsed,
+            None => profile.phases.push((name, elapsed)),
+        }
+    });
+}
+
+/// Records that the first frame has been drawn, which ends startup.
+pub(crate) fn ready() {
+    with_profile(|profile| {
+        if profile.ready.is_none() {
+            profile.ready = Some(profile.start.elapsed());
+        }
+    });
+}
+
+/// Records the time since a key press was read.
+pub(crate) fn key(start: Instant) {
+    let elapsed = start.elapsed();
+    with_profile(|profile| profile.keys.push(elapsed));
+}
+
+/// Writes the startup phases and key press latency percentiles, if profiling is enabled.
+pub(crate) fn report(out: &mut impl Write) -> io::Result<()> {
+    let profile = match PROFILE.lock() {
+        Ok(profile) => profile,
+        Err(_) => return Ok(()),
+    };
+    let profile = match profile.as_ref() {
+        Some(profile) => profile,
+        None => return Ok(()),
+    };
+
+    writeln!(out, "startup:")?;
+    for (name, duration) in &profile.phases {
+        writeln!(out, "  {name:<18} {}", millis(*duration))?;
+    }
+    if let Some(ready) = profile.ready {
+        writeln!(out, "  {:<18} {}", "total", millis(ready))?;
+    }
+
+    let mut keys = profile.keys.clone();
+    keys.sort();
+    writeln!(out, "key presses: {}", keys.len())?;
+    if keys.is_empty() {
+        return Ok(());
+    }
+    for (name, percentile) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)] {
+        let index = ((keys.len() - 1) as f64 * percentile).round() as usize;
+        writeln!(out, "  {name:<18} {}", millis(keys[index]))?;
+    }
+    Ok(())
+}
+
+/// Formats a duration in milliseconds, right-aligned.
+fn millis(duration: Duration) -> String {
+    format!("{:>9.3} ms", duration.as_secs_f64() * 1000.0)
+}
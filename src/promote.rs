@@ -0,0 +1,118 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Writes entries into the user's config file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use toml_edit::{value, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::config;
+
+/// Adds an entry to a menu in the config at `path`, preserving the file's formatting and
+/// comments. Existing entries with the same name are left alone.
+///
+/// If the config consists of several files, the entry is written to the last TOML file that
+/// defines the menu, or else the last TOML file.
+pub(crate) fn promote(path: &Path, menu: &str, name: &str, entry_value: &str) -> Result<()> {
+    let mut documents = documents(path)?;
+    let defines_menu = |document: &DocumentMut| {
+        document
+            .get("menus")
+            .and_then(|menus| menus.get(menu))
+            .is_some()
+    };
+    let index = documents
+        .iter()
+        .rposition(|(_, document)| defines_menu(document))
+        .or_else(|| documents.len().checked_sub(1))
+        .ok_or_else(|| anyhow!("No TOML config file to add the entry to"))?;
+    let (file, document) = &mut documents[index];
+
+    let entries = table(document.as_table_mut(), "menus")
+        .and_then(|menus| table(menus, menu))
+        .and_then(|menu| table(menu, "entries"))
+        .with_context(|| format!("Menu '{menu}' in '{}' isn't a table", file.display()))?;
+    if !entries.contains_key(name) {
+        entries.insert(name, value(entry_value));
+    }
+
+    fs::write(&file, document.to_string())
+        .with_context(|| format!("Failed to write config file '{}'", file.display()))
+}
+
+/// Sets `disabled = true` on an entry of a menu in the config at `path`, preserving the file's
+/// formatting and comments. Entries written as just their value are turned into inline tables.
+///
+/// If the config consists of several files, the entry is changed in the last TOML file that
+/// defines it.
+pub(crate) fn disable(path: &Path, menu: &str, name: &str) -> Result<()> {
+    let mut documents = documents(path)?;
+    let (file, document) = documents
+        .iter_mut()
+        .rev()
+        .find(|(_, document)| {
+            document
+                .get("menus")
+                .and_then(|menus| menus.get(menu))
+                .and_then(|menu| menu.get("entries"))
+                .and_then(|entries| entries.get(name))
+                .is_some()
+        })
+        .ok_or_else(|| anyhow!("No TOML config file defines entry '{name}' of menu '{menu}'"))?;
+
+    let entry = &mut document["menus"][menu]["entries"][name];
+    if let Some(Value::String(entry_value)) = entry.as_value() {
+        // Keep the whitespace and comment around the value
+        let decor = entry_value.decor().clone();
+        let mut table = InlineTable::new();
+        table.insert("value", entry_value.value().as_str().into());
+        *table.decor_mut() = decor;
+        *entry = Item::Value(Value::InlineTable(table));
+    }
+    match entry {
+        Item::Table(table) => {
+            table.insert("disabled", value(true));
+        }
+        Item::Value(Value::InlineTable(table)) => {
+            table.insert("disabled", true.into());
+        }
+        _ => bail!("Entry '{name}' of menu '{menu}' isn't a string or a table"),
+    }
+
+    fs::write(&file, document.to_string())
+        .with_context(|| format!("Failed to write config file '{}'", file.display()))
+}
+
+/// Reads and parses the TOML files of the config at `path`, in order of increasing precedence.
+fn documents(path: &Path) -> Result<Vec<(PathBuf, DocumentMut)>> {
+    let files: Vec<_> = config::config_files(path)?
+        .into_iter()
+        .filter(|file| matches!(config::config_format(file), Ok(::config::FileFormat::Toml)))
+        .collect();
+
+    let mut documents = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read config file '{}'", file.display()))?;
+        let document: DocumentMut = text
+            .parse()
+            .with_context(|| format!("Failed to parse config file '{}'", file.display()))?;
+        documents.push((file, document));
+    }
+    Ok(documents)
+}
+
+/// Returns the table at `key`, creating it if it doesn't exist.
+fn table<'a>(parent: &'a mut Table, key: &str) -> Option<&'a mut Table> {
+    parent
+        .entry(key)
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_mut()
+}
@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Records key chords as keybinds.
+
+use std::io::{stderr, Write};
+
+use anyhow::Result;
+use crossterm::{
+    event::{read, Event},
+    terminal::{disable_raw_mode, enable_raw_mode},
+};
+
+use crate::keybinds::Keybind;
+
+/// Waits for a key chord to be pressed, and returns it as a keybind. Modifier keys on their own
+/// aren't reported by the terminal, so they're only recorded along with another key.
+pub(crate) fn record_keybind() -> Result<Keybind> {
+    write!(stderr(), "Press a key chord...\r\n")?;
+    enable_raw_mode()?;
+    let event = loop {
+        match read() {
+            Ok(Event::Key(event)) => break Ok(event),
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    disable_raw_mode()?;
+    Ok(Keybind::from_event(event?))
+}
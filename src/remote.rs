@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Fetches config files included from remote URLs.
+//!
+//! Fetched files are cached in `$XDG_CACHE_HOME/fr33zmenu/remote` along with their ETags, so that
+//! they're only downloaded again when they've changed, and are still available while offline.
+
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use anyhow::Result;
+#[cfg(feature = "remote")]
+use {
+    crate::util,
+    anyhow::{anyhow, bail, Context},
+    std::{fs, time::Duration},
+};
+
+/// Set by `--offline`, in which case only cached files are used.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Makes [fetch] only use cached files, without accessing the network.
+pub(crate) fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Returns the path of the cached file for a URL. The URL is turned into a file name that keeps
+/// its extension, which determines the format of the file.
+#[cfg(feature = "remote")]
+fn cache_path(url: &str) -> Result<PathBuf> {
+    let name: String = url
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    util::xdg_dir("XDG_CACHE_HOME", ".cache")
+        .map(|dir| dir.join("fr33zmenu/remote").join(name))
+        .ok_or_else(|| anyhow!("Failed to find a cache directory for remote includes"))
+}
+
+/// Fetches the file at `url` into the cache, and returns the cached file. The file is revalidated
+/// with its ETag if it's already cached, and the cached file is used as it is if the URL can't be
+/// reached, or with `--offline`.
+#[cfg(feature = "remote")]
+pub(crate) fn fetch(url: &str) -> Result<PathBuf> {
+    /// How long to wait for a response before falling back to the cached file.
+    const TIMEOUT_SECS: u64 = 5;
+
+    let cache = cache_path(url)?;
+    let mut etag_name = cache.file_name().unwrap_or_default().to_os_string();
+    etag_name.push(".etag");
+    let etag_path = cache.with_file_name(etag_name);
+    let cached = cache.is_file();
+
+    if OFFLINE.load(Ordering::Relaxed) {
+        if !cached {
+            bail!("Included URL '{url}' isn't cached, so it can't be used with --offline");
+        }
+        return Ok(cache);
+    }
+
+    let mut request = ureq::get(url).timeout(Duration::from_secs(TIMEOUT_SECS));
+    if cached {
+        if let Ok(etag) = fs::read_to_string(&etag_path) {
+            request = request.set("If-None-Match", etag.trim());
+        }
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        // The cached file may be outdated, but it's better than failing to start
+        Err(_) if cached => return Ok(cache),
+        Err(e) => return Err(e).with_context(|| format!("Failed to fetch included URL '{url}'")),
+    };
+    if response.status() == 304 {
+        return Ok(cache); // Not modified
+    }
+
+    let etag = response.header("ETag").map(str::to_string);
+    let body = response
+        .into_string()
+        .with_context(|| format!("Failed to read included URL '{url}'"))?;
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create cache directory '{}'", parent.display()))?;
+    }
+    util::write_atomic(&cache, body)
+        .with_context(|| format!("Failed to cache included URL '{url}'"))?;
+    match etag {
+        Some(etag) => {
+            let _ = util::write_atomic(&etag_path, etag);
+        }
+        None => {
+            let _ = fs::remove_file(&etag_path);
+        }
+    }
+    Ok(cache)
+}
+
+/// Remote includes aren't available without the `remote` feature.
+#[cfg(not(feature = "remote"))]
+pub(crate) fn fetch(url: &str) -> Result<PathBuf> {
+    anyhow::bail!("Can't include '{url}', because fr33zmenu was built without the remote feature")
+}
@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Executes entries named on the command line, without starting the interface.
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{
+    args::Args,
+    config::{self, EntryKind},
+    exec, history,
+};
+
+/// Loads the config at the provided path (see [config::config_path]), and executes each of the
+/// named entries, written as `<menu>/<entry>`, in order. Entries are executed with
+/// `--exec-with` if it's given, or else as with `--exec`, and link entries are opened with the
+/// `opener` option, just as when they're submitted in the interface. Every name is looked up
+/// before anything is executed, so that a typo doesn't leave the batch half done.
+pub(crate) fn run(args: &Args, path: PathBuf, names: &[String]) -> Result<()> {
+    let config = config::load_config(
+        Some(config::config_path(Some(path))?),
+        args.theme.as_deref(),
+    )?;
+    let mut commands = Vec::with_capacity(names.len());
+    for name in names {
+        let (menu_name, entry_name) = name
+            .split_once('/')
+            .ok_or_else(|| anyhow!("'{name}' isn't written as <menu>/<entry>"))?;
+        let menu = &config
+            .menus
+            .iter()
+            .find(|menu| menu.0 == menu_name)
+            .ok_or_else(|| anyhow!("Menu '{menu_name}' doesn't exist"))?
+            .1;
+        let entry = &menu
+            .entries
+            .iter()
+            .find(|entry| entry.0 == entry_name)
+            .ok_or_else(|| anyhow!("Menu '{menu_name}' has no entry '{entry_name}'"))?
+            .1;
+        if entry.info || !entry.kind.is_submittable() || entry.kind == EntryKind::Submenu {
+            bail!("Entry '{name}' can't be executed");
+        }
+        let command = match entry.kind {
+            EntryKind::Link => exec::open_link(&config.options.opener, &entry.value)?,
+            _ => match exec::command(args, &entry.value)? {
+                Some(command) => command,
+                None => exec::execute(&entry.value),
+            },
+        };
+        commands.push((name, menu_name, entry_name, command));
+    }
+
+    for (name, menu_name, entry_name, mut command) in commands {
+        command
+            .spawn()
+            .with_context(|| format!("Failed to execute '{name}'"))?;
+        // As in the interface, failing to record to the history isn't an error
+        let _ = history::record(menu_name, entry_name);
+    }
+    Ok(())
+}
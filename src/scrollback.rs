@@ -0,0 +1,113 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! A log of the selections executed in stay-open mode.
+
+use std::{
+    collections::VecDeque,
+    io,
+    process::Child,
+    time::{Duration, Instant},
+};
+
+/// The number of executions shown at the bottom of the interface.
+pub(crate) const SCROLLBACK_ROWS: usize = 3;
+
+/// The outcome of an executed selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    /// The command is still running.
+    Running,
+
+    /// The command exited successfully.
+    Success,
+
+    /// The command couldn't be spawned, or exited unsuccessfully. Holds a short reason.
+    Failure(String),
+}
+
+/// An executed selection.
+#[derive(Debug)]
+pub(crate) struct Execution {
+    /// The name of the executed entry.
+    pub(crate) name: String,
+
+    /// The outcome of the command.
+    pub(crate) outcome: Outcome,
+
+    /// The command's process, until it exits.
+    child: Option<Child>,
+
+    /// How long the command may run before it's killed, and when it was spawned.
+    timeout: Option<(Duration, Instant)>,
+}
+
+/// A log of executed selections, newest last.
+#[derive(Debug, Default)]
+pub(crate) struct Scrollback {
+    executions: VecDeque<Execution>,
+}
+
+impl Scrollback {
+    /// Records an execution, given the result of spawning its command. If it's still running
+    /// after `timeout`, the command is killed.
+    pub(crate) fn push(
+        &mut self,
+        name: String,
+        spawned: io::Result<Child>,
+        timeout: Option<Duration>,
+    ) {
+        let (outcome, child) = match spawned {
+            Ok(child) => (Outcome::Running, Some(child)),
+            Err(e) => (Outcome::Failure(e.to_string()), None),
+        };
+        self.executions.push_back(Execution {
+            name,
+            outcome,
+            child,
+            timeout: timeout.map(|timeout| (timeout, Instant::now())),
+        });
+
+        // Forget old executions, but keep waiting on those that are still running.
+        while self.executions.len() > SCROLLBACK_ROWS {
+            match self.executions.iter().position(|e| e.child.is_none()) {
+                Some(i) => self.executions.remove(i),
+                None => break,
+            };
+        }
+    }
+
+    /// Checks whether running commands have exited, and kills those that have run out of time.
+    /// Returns `true` if any outcome changed.
+    pub(crate) fn poll(&mut self) -> bool {
+        let mut changed = false;
+        for execution in self.executions.iter_mut() {
+            let child = match &mut execution.child {
+                Some(child) => child,
+                None => continue,
+            };
+            let outcome = match child.try_wait() {
+                Ok(None) => match execution.timeout {
+                    Some((timeout, spawned)) if spawned.elapsed() >= timeout => {
+                        // Reap the process so it doesn't linger as a zombie
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        Outcome::Failure(format!("timed out after {}s", timeout.as_secs()))
+                    }
+                    _ => continue,
+                },
+                Ok(Some(status)) if status.success() => Outcome::Success,
+                Ok(Some(status)) => Outcome::Failure(status.to_string()),
+                Err(e) => Outcome::Failure(e.to_string()),
+            };
+            execution.outcome = outcome;
+            execution.child = None;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Returns the most recent executions, oldest first.
+    pub(crate) fn recent(&self) -> impl Iterator<Item = &Execution> {
+        let skip = self.executions.len().saturating_sub(SCROLLBACK_ROWS);
+        self.executions.iter().skip(skip)
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Login session chooser mode.
+
+use std::{os::unix::process::CommandExt, process::Command};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    args::Args,
+    config::{self, Config, Menu},
+    sources,
+};
+
+/// Loads the config used to choose a session: a single menu of sessions, themed and bound
+/// according to the user's config if one exists.
+///
+/// The exit keybinds are removed, so that the chooser can't be left without starting a session.
+/// The promote, disable, and reload keybinds are removed too, since sessions aren't in the config,
+/// and the recent menu is left out. Custom actions are removed, since they'd run the session with
+/// another command.
+pub(crate) fn config(args: &Args) -> Result<Config> {
+    let mut config = config::load_config(
+        config::config_path(args.config.clone()).ok(),
+        args.theme.as_deref(),
+    )?;
+    let mut menu = Menu {
+        prompt: "session -> ".to_string(),
+        source: Some("sessions".to_string()),
+        ..Default::default()
+    };
+    sources::generate("sessions", &mut menu)?;
+    config.menus = vec![("sessions".to_string(), menu)];
+    config.keybinds.exit.clear();
+    config.keybinds.promote.clear();
+    config.keybinds.disable.clear();
+    config.keybinds.reload.clear();
+    config.actions.clear();
+    config.options.recent_menu = 0;
+    Ok(config)
+}
+
+/// Replaces the program with the selected session. Only returns if the session can't be
+/// started.
+pub(crate) fn exec(selection: &str) -> Result<()> {
+    let error = Command::new("/bin/sh")
+        .arg("-c")
+        .arg(format!("exec {selection}"))
+        .exec();
+    Err(error).context("Failed to start session")
+}
@@ -0,0 +1,170 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Upcoming calendar events from an `.ics` file or `khal`.
+
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::{Entry, Menu},
+    util,
+};
+
+/// The number of days, starting today, for which events are listed.
+const AGENDA_DAYS: u32 = 7;
+
+/// Generates a read-only entry for each upcoming event. The entry name is the event's start and
+/// title, and the value holds its details. The entries are informational, so submitting one
+/// doesn't run its details as a command.
+///
+/// Events are read from the menu's `path` if it is set, otherwise from `khal list`.
+pub(super) fn entries(menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let mut entries = match &menu.path {
+        Some(path) => ics_entries(path)?,
+        None => khal_entries()?,
+    };
+    for (_, entry) in &mut entries {
+        entry.info = true;
+    }
+    Ok(entries)
+}
+
+/// A calendar event.
+#[derive(Debug, Default)]
+struct Event {
+    /// The start date and time, as `YYYYMMDD` optionally followed by `THHMMSS`.
+    start: String,
+    summary: String,
+    location: String,
+    description: String,
+}
+
+impl Event {
+    /// Formats the event as a `(name, value)` entry.
+    fn into_entry(self) -> (String, Entry) {
+        let date = self.start.get(..8).unwrap_or_default();
+        let mut name = match (date.get(..4), date.get(4..6), date.get(6..8)) {
+            (Some(y), Some(m), Some(d)) => format!("{y}-{m}-{d}"),
+            _ => self.start.clone(),
+        };
+        if let (Some(h), Some(m)) = (self.start.get(9..11), self.start.get(11..13)) {
+            name.push_str(&format!(" {h}:{m}"));
+        }
+        name.push_str(&format!(" {}", self.summary));
+
+        let details: Vec<&str> = [&self.summary, &self.location, &self.description]
+            .into_iter()
+            .map(String::as_str)
+            .filter(|s| !s.is_empty())
+            .collect();
+        (name, details.join(" - ").into())
+    }
+}
+
+/// Reads events from an iCalendar file.
+fn ics_entries(path: &Path) -> Result<Vec<(String, Entry)>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read calendar '{}'", path.display()))?;
+
+    let now = util::unix_time() / 86400;
+    let (first, last) = (date_string(now), date_string(now + AGENDA_DAYS as u64));
+
+    let mut entries = Vec::new();
+    let mut event: Option<Event> = None;
+    for line in unfold(&contents) {
+        let (key, value) = match line.split_once(':') {
+            Some(kv) => kv,
+            None => continue,
+        };
+        // Drop parameters, e.g. `DTSTART;TZID=Europe/Berlin`
+        let key = key.split(';').next().unwrap_or_default();
+
+        match key {
+            "BEGIN" if value == "VEVENT" => event = Some(Event::default()),
+            "END" if value == "VEVENT" => {
+                if let Some(e) = event.take() {
+                    let date = e.start.get(..8).unwrap_or_default();
+                    if date >= first.as_str() && date < last.as_str() {
+                        entries.push(e.into_entry());
+                    }
+                }
+            }
+            _ => {
+                if let Some(e) = event.as_mut() {
+                    match key {
+                        "DTSTART" => e.start = value.to_string(),
+                        "SUMMARY" => e.summary = unescape(value),
+                        "LOCATION" => e.location = unescape(value),
+                        "DESCRIPTION" => e.description = unescape(value),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Reads events from the output of `khal list`.
+fn khal_entries() -> Result<Vec<(String, Entry)>> {
+    let output = Command::new("khal")
+        .args([
+            "list",
+            "--day-format",
+            "",
+            "--format",
+            "{start-date} {start-time}\t{title}\t{location}\t{description}",
+            "today",
+            &format!("{AGENDA_DAYS}d"),
+        ])
+        .output()
+        .context("Failed to run khal")?;
+    if !output.status.success() {
+        bail!("khal exited with {}", output.status);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let entries = stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.split('\t').map(str::trim);
+            let start = fields.next().unwrap_or_default();
+            let details: Vec<&str> = fields.filter(|f| !f.is_empty()).collect();
+            let title = details.first().copied().unwrap_or_default();
+            (format!("{start} {title}"), details.join(" - ").into())
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Formats a number of days since the Unix epoch as `YYYYMMDD`.
+fn date_string(days: u64) -> String {
+    let (y, m, d) = util::civil_from_days(days as i64);
+    format!("{y:04}{m:02}{d:02}")
+}
+
+/// Joins folded content lines, i.e. lines continued by a leading space or tab.
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (
+            line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')),
+            lines.last_mut(),
+        ) {
+            (Some(cont), Some(last)) => last.push_str(cont),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Unescapes an iCalendar text value.
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Aliases and functions defined in the user's shell.
+
+use std::{
+    collections::HashSet,
+    env,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::{Entry, Menu},
+    util::quote,
+};
+
+/// How long the shell may take to read its rc files and list the aliases before it's killed, so
+/// that a slow or hung rc file doesn't keep the menu from opening.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The shells whose aliases and functions can be listed, and how to list them.
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl Shell {
+    /// The script that prints the aliases, then a line with just `--`, then the function names.
+    fn script(&self) -> &'static str {
+        match self {
+            Shell::Bash => "alias; echo --; compgen -A function",
+            Shell::Zsh => "alias -L; echo --; print -l ${(k)functions}",
+            Shell::Fish => "alias; echo --; functions -n",
+        }
+    }
+}
+
+/// Generates an entry for each alias and function of the shell in `$SHELL`, which runs it
+/// through an interactive shell, so that the shell's rc files are read. Aliases are described by
+/// what they expand to. Functions whose names start with `_` or `fish_` are left out, since
+/// they're usually internal to plugins and completions.
+///
+/// The entries are prefixed with the menu's `terminal` command if it has one, since aliases are
+/// usually used in a terminal.
+pub(super) fn entries(menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let shell_path = env::var("SHELL").context("$SHELL isn't set")?;
+    let shell = match Path::new(&shell_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+    {
+        Some("bash") => Shell::Bash,
+        Some("zsh") => Shell::Zsh,
+        Some("fish") => Shell::Fish,
+        _ => bail!("unsupported shell '{shell_path}', expected bash, zsh, or fish"),
+    };
+
+    let output = list(&shell_path, &shell)?;
+    let output = String::from_utf8_lossy(&output);
+    let mut lines = output.lines();
+    let aliases: Vec<&str> = lines.by_ref().take_while(|line| *line != "--").collect();
+
+    let command = |name: &str| {
+        let command = format!("{shell_path} -ic {}", quote(name));
+        match &menu.terminal {
+            Some(terminal) => format!("{terminal} {command}"),
+            None => command,
+        }
+    };
+    let mut seen = HashSet::new();
+    let mut entries = Vec::new();
+    for (name, expansion) in aliases.iter().filter_map(|line| parse_alias(&shell, line)) {
+        if seen.insert(name.clone()) {
+            let entry = Entry {
+                desc: Some(expansion),
+                ..Entry::from(command(&name))
+            };
+            entries.push((name, entry));
+        }
+    }
+    // fish lists the names separated by commas when they're not on their own lines
+    let functions = lines
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.starts_with('_') && !name.starts_with("fish_"));
+    for name in functions {
+        if seen.insert(name.to_string()) {
+            let entry = Entry {
+                desc: Some("function".to_string()),
+                ..Entry::from(command(name))
+            };
+            entries.push((name.to_string(), entry));
+        }
+    }
+    Ok(entries)
+}
+
+/// Runs the shell's listing script in an interactive shell, and returns what it printed. The
+/// output is read on another thread, so that the shell can be killed if it's still running after
+/// [TIMEOUT].
+fn list(shell_path: &str, shell: &Shell) -> Result<Vec<u8>> {
+    let mut child = Command::new(shell_path)
+        .args(["-i", "-c", shell.script()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {shell_path}"))?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("The shell's output wasn't captured")?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        let _ = sender.send(output);
+    });
+    match receiver.recv_timeout(TIMEOUT) {
+        Ok(output) => {
+            let _ = child.wait();
+            Ok(output)
+        }
+        Err(_) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("{shell_path} didn't list its aliases within {TIMEOUT:?}; check its rc files")
+        }
+    }
+}
+
+/// Parses an alias as it's listed by the shell, e.g. `alias ll='ls -l'` in bash and zsh, or
+/// `alias ll 'ls -l'` in fish, into its name and expansion.
+fn parse_alias(shell: &Shell, line: &str) -> Option<(String, String)> {
+    let line = line.strip_prefix("alias ")?;
+    let line = line.strip_prefix("-- ").unwrap_or(line);
+    let (name, expansion) = match shell {
+        Shell::Fish => line.split_once(' ')?,
+        Shell::Bash | Shell::Zsh => line.split_once('=')?,
+    };
+    Some((unquote(name), unquote(expansion)))
+}
+
+/// Removes the quotes around a shell word, as the shell prints it.
+fn unquote(s: &str) -> String {
+    if let Some(s) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        s.replace(r"'\''", "'")
+    } else if let Some(s) = s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        s.replace("\\\"", "\"")
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,161 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Applications from XDG `.desktop` files.
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+use crate::{
+    config::{Entry, Menu},
+    util,
+};
+
+/// The fields of a desktop entry that are relevant to the launcher.
+#[derive(Debug, Default)]
+pub(crate) struct DesktopEntry {
+    /// The unlocalized name.
+    pub(crate) name: String,
+
+    /// The command line, with field codes removed.
+    pub(crate) exec: String,
+
+    /// Indicates that the entry should not be shown in menus.
+    pub(crate) no_display: bool,
+
+    /// Indicates that the program must be run in a terminal.
+    pub(crate) terminal: bool,
+}
+
+/// Generates an entry for each visible application.
+///
+/// Applications that must run in a terminal are prefixed with the menu's `terminal` command, or
+/// skipped if the menu doesn't have one.
+pub(super) fn entries(menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let mut files = HashMap::new();
+    // Lowest precedence first, so that files with the same ID are overwritten.
+    for dir in application_dirs().iter().rev() {
+        collect(dir, dir, &mut files);
+    }
+
+    let mut entries = Vec::new();
+    for path in files.values() {
+        let entry = match parse(path) {
+            Ok(Some(entry)) => entry,
+            _ => continue,
+        };
+        if entry.no_display {
+            continue;
+        }
+        let exec = if entry.terminal {
+            match &menu.terminal {
+                Some(terminal) => format!("{terminal} {}", entry.exec),
+                None => continue,
+            }
+        } else {
+            entry.exec
+        };
+        entries.push((entry.name, exec.into()));
+    }
+    Ok(entries)
+}
+
+/// Returns the XDG application directories, in order of precedence.
+fn application_dirs() -> Vec<PathBuf> {
+    data_dirs()
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect()
+}
+
+/// Returns the XDG data directories, in order of precedence.
+pub(super) fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = util::xdg_dir("XDG_DATA_HOME", ".local/share")
+        .into_iter()
+        .collect();
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    );
+    dirs
+}
+
+/// Recursively collects the `.desktop` files in `dir`, keyed by their desktop file ID.
+pub(crate) fn collect(root: &Path, dir: &Path, files: &mut HashMap<String, PathBuf>) {
+    let read_dir = match fs::read_dir(dir) {
+        Ok(read_dir) => read_dir,
+        Err(_) => return,
+    };
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if path.is_dir() {
+            collect(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let id = relative.to_string_lossy().replace('/', "-");
+                files.insert(id, path);
+            }
+        }
+    }
+}
+
+/// Parses the `[Desktop Entry]` group of a desktop file. Returns `None` if the entry is hidden,
+/// is not an application or session, or is missing a name or command.
+pub(crate) fn parse(path: &Path) -> Result<Option<DesktopEntry>> {
+    let contents = fs::read_to_string(path)?;
+    let mut entry = DesktopEntry::default();
+    let mut in_group = false;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_group || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => continue,
+        };
+        match key {
+            "Type" if !matches!(value, "Application" | "XSession") => return Ok(None),
+            "Hidden" if value == "true" => return Ok(None),
+            "Name" => entry.name = value.to_string(),
+            "Exec" => entry.exec = strip_field_codes(value),
+            "NoDisplay" => entry.no_display = value == "true",
+            "Terminal" => entry.terminal = value == "true",
+            _ => {}
+        }
+    }
+
+    if entry.name.is_empty() || entry.exec.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(entry))
+    }
+}
+
+/// Removes field codes (e.g. `%f`, `%U`) from an `Exec` value, and unescapes `%%`.
+fn strip_field_codes(exec: &str) -> String {
+    let mut stripped = String::with_capacity(exec.len());
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            stripped.push(c);
+        } else if let Some('%') = chars.next() {
+            stripped.push('%');
+        }
+    }
+    stripped.split_whitespace().collect::<Vec<_>>().join(" ")
+}
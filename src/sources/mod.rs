@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Generates menu entries from sources.
+//!
+//! Each source is a [Provider], registered under the name that a menu's `source` selects it by.
+//! Config loading and the interface only go through [generate] and [refresh], so a new source
+//! only needs a provider: a built-in one is added to [BUILTINS], and any other is passed to
+//! [register] at startup.
+//!
+//! fr33zmenu is only built as a binary, so providers can only be registered from within the
+//! crate. Sources from elsewhere are added as built-ins, or write a menu file into a config
+//! directory, which is read along with the rest of the config.
+
+use std::{
+    collections::HashMap,
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::{
+    config::{Entry, Menu},
+    util,
+};
+
+mod agenda;
+mod aliases;
+mod desktop;
+mod path;
+mod sessions;
+mod sysinfo;
+mod workspaces;
+
+/// Seconds between refreshing `sysinfo` menus that don't specify `refresh`.
+const SYSINFO_REFRESH: u64 = 5;
+
+/// Generates the entries of menus whose `source` is its name.
+///
+/// A provider only has to generate entries; [generate] marks them as generated and replaces the
+/// ones it generated before, and [refresh] regenerates them while the menu is open.
+pub(crate) trait Provider: Sync {
+    /// The `source` that selects the provider.
+    fn name(&self) -> &str;
+
+    /// Generates the entries of a menu.
+    fn generate(&self, menu: &Menu) -> Result<Vec<(String, Entry)>>;
+
+    /// Returns the number of seconds between regenerating a menu's entries while it's open, or
+    /// `None` if they're only generated when the config is loaded. Defaults to the menu's
+    /// `refresh`.
+    fn refresh(&self, menu: &Menu) -> Option<u64> {
+        menu.refresh
+    }
+
+    /// Returns which of the menu's settings the provider reads.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+}
+
+/// The settings of a menu that a provider reads, besides `source` and `refresh`. `fr33zmenu
+/// check` warns about those that are set but not read.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Capabilities {
+    /// Indicates that the menu's `path` is read.
+    pub(crate) path: bool,
+
+    /// Indicates that the menu's `terminal` is used to run generated entries.
+    pub(crate) terminal: bool,
+}
+
+/// A function that generates the entries of a menu.
+type Generator = fn(&Menu) -> Result<Vec<(String, Entry)>>;
+
+/// A provider built into fr33zmenu, whose entries are generated by a function of its module.
+struct Builtin {
+    name: &'static str,
+    generate: Generator,
+    refresh: Option<u64>,
+    capabilities: Capabilities,
+}
+
+impl Provider for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn generate(&self, menu: &Menu) -> Result<Vec<(String, Entry)>> {
+        (self.generate)(menu)
+    }
+
+    fn refresh(&self, menu: &Menu) -> Option<u64> {
+        menu.refresh.or(self.refresh)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+}
+
+/// The built-in providers. To add one, add a module that generates its entries, and list it here.
+static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "agenda",
+        generate: agenda::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: true,
+            terminal: false,
+        },
+    },
+    Builtin {
+        name: "aliases",
+        generate: aliases::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: false,
+            terminal: true,
+        },
+    },
+    Builtin {
+        name: "desktop",
+        generate: desktop::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: false,
+            terminal: true,
+        },
+    },
+    Builtin {
+        name: "path",
+        generate: path::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: false,
+            terminal: false,
+        },
+    },
+    Builtin {
+        name: "sessions",
+        generate: sessions::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: false,
+            terminal: false,
+        },
+    },
+    Builtin {
+        name: "sysinfo",
+        generate: sysinfo::entries,
+        refresh: Some(SYSINFO_REFRESH),
+        capabilities: Capabilities {
+            path: false,
+            terminal: false,
+        },
+    },
+    Builtin {
+        name: "workspaces",
+        generate: workspaces::entries,
+        refresh: None,
+        capabilities: Capabilities {
+            path: false,
+            terminal: false,
+        },
+    },
+];
+
+/// The registered providers. Names are unique.
+static PROVIDERS: RwLock<Vec<&'static dyn Provider>> = RwLock::new(Vec::new());
+
+/// Registers a provider, so that menus whose `source` is its name are generated by it. Fails if
+/// a provider with the same name is already registered.
+pub(crate) fn register(provider: &'static dyn Provider) -> Result<()> {
+    let mut providers = PROVIDERS
+        .write()
+        .map_err(|_| anyhow!("The source registry was poisoned"))?;
+    if providers.iter().any(|p| p.name() == provider.name()) {
+        bail!("A source named '{}' is already registered", provider.name());
+    }
+    providers.push(provider);
+    Ok(())
+}
+
+/// Registers the built-in providers. Called once at startup, before any config is loaded.
+pub(crate) fn register_builtins() -> Result<()> {
+    BUILTINS.iter().try_for_each(|builtin| register(builtin))
+}
+
+/// Returns the provider registered for a `source`.
+pub(crate) fn provider(source: &str) -> Option<&'static dyn Provider> {
+    PROVIDERS
+        .read()
+        .expect("the source registry is only written at startup, before it's read")
+        .iter()
+        .find(|provider| provider.name() == source)
+        .copied()
+}
+
+/// Generates entries for a menu with a `source`, replacing any it generated previously.
+pub(crate) fn generate(name: &str, menu: &mut Menu) -> Result<()> {
+    let source = match &menu.source {
+        Some(source) => source,
+        None => return Ok(()),
+    };
+    let entries = provider(source)
+        .ok_or_else(|| anyhow!("unknown source '{source}'"))
+        .and_then(|provider| provider.generate(menu))
+        .with_context(|| format!("Failed to generate entries for menu '{name}'"))?;
+
+    menu.entries.retain(|(_, entry)| !entry.generated);
+    menu.entries
+        .extend(entries.into_iter().map(|(name, entry)| {
+            (
+                name,
+                Entry {
+                    generated: true,
+                    ..entry
+                },
+            )
+        }));
+    menu.generated_at = Some(Instant::now());
+    Ok(())
+}
+
+/// Regenerates a menu's entries if its refresh interval has elapsed. Returns `true` if the
+/// entries were regenerated.
+pub(crate) fn refresh(name: &str, menu: &mut Menu) -> Result<bool> {
+    let interval = match menu.source.as_deref().and_then(provider) {
+        Some(provider) => match provider.refresh(menu) {
+            Some(secs) => secs,
+            None => return Ok(false),
+        },
+        None => return Ok(false),
+    };
+    let due = menu
+        .generated_at
+        .is_none_or(|at| at.elapsed() >= Duration::from_secs(interval));
+    if due {
+        // Frecency is read from the history when the config is loaded, so it's carried over
+        let frecency: HashMap<String, u64> = menu
+            .entries
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.frecency))
+            .collect();
+        generate(name, menu)?;
+        for (name, entry) in menu.entries.iter_mut() {
+            entry.frecency = frecency.get(name).copied().unwrap_or_default();
+        }
+        util::assign_hotkeys(menu);
+    }
+    Ok(due)
+}
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Executables on `$PATH`, similar to `dmenu_path`.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+
+use crate::{
+    config::{Entry, Menu},
+    util,
+};
+
+/// Generates an entry for each executable on `$PATH`. The entry name and value are both the
+/// executable's file name.
+///
+/// The list is cached, and only rebuilt when `$PATH` or one of its directories has changed
+/// since the cache was written.
+pub(super) fn entries(_menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let path = env::var("PATH").unwrap_or_default();
+    let cache = util::xdg_dir("XDG_CACHE_HOME", ".cache").map(|dir| dir.join("fr33zmenu/path"));
+
+    let names = match cache.as_deref().and_then(|cache| read_cache(cache, &path)) {
+        Some(names) => names,
+        None => {
+            let names = scan(&path);
+            if let Some(cache) = &cache {
+                // The cache is an optimization, so failing to write it isn't an error.
+                let _ = write_cache(cache, &path, &names);
+            }
+            names
+        }
+    };
+
+    Ok(names
+        .into_iter()
+        .map(|name| (name.clone(), name.into()))
+        .collect())
+}
+
+/// Returns the directories on `$PATH`.
+fn path_dirs(path: &str) -> impl Iterator<Item = PathBuf> + '_ {
+    path.split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Lists the names of all executable files on `$PATH`.
+fn scan(path: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for dir in path_dirs(path) {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => continue,
+        };
+        for item in read_dir.flatten() {
+            let executable = fs::metadata(item.path())
+                .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false);
+            if executable {
+                names.insert(item.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names
+}
+
+/// Reads the cached executable names. Returns `None` if the cache is missing or stale.
+///
+/// The first line of the cache holds the `$PATH` it was built from.
+fn read_cache(cache: &Path, path: &str) -> Option<BTreeSet<String>> {
+    let modified = |p: &Path| fs::metadata(p).and_then(|meta| meta.modified()).ok();
+    let cache_modified = modified(cache)?;
+    let stale = path_dirs(path)
+        .any(|dir| modified(&dir).is_some_and(|dir_modified| dir_modified > cache_modified));
+    if stale || cache_modified > SystemTime::now() {
+        return None;
+    }
+
+    let contents = fs::read_to_string(cache).ok()?;
+    let mut lines = contents.lines();
+    if lines.next()? != path {
+        return None;
+    }
+    Some(lines.map(String::from).collect())
+}
+
+/// Writes the executable names to the cache.
+fn write_cache(cache: &Path, path: &str, names: &BTreeSet<String>) -> Result<()> {
+    if let Some(parent) = cache.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut contents = format!("{path}\n");
+    for name in names {
+        contents.push_str(name);
+        contents.push('\n');
+    }
+    // Another instance may be reading the cache
+    util::write_atomic(cache, contents)?;
+    Ok(())
+}
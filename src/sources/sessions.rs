@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Login sessions from the installed Wayland and X session files.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use super::desktop;
+use crate::config::{Entry, Menu};
+
+/// Generates an entry for each installed session. X sessions are started with `startx`, and
+/// suffixed with `(X11)` to tell them apart from their Wayland counterparts.
+pub(super) fn entries(_menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let mut entries = Vec::new();
+    for (subdir, x11) in [("wayland-sessions", false), ("xsessions", true)] {
+        let mut files = HashMap::new();
+        // Lowest precedence first, so that files with the same ID are overwritten.
+        for dir in desktop::data_dirs().iter().rev() {
+            let dir = dir.join(subdir);
+            desktop::collect(&dir, &dir, &mut files);
+        }
+
+        for path in files.values() {
+            let session = match desktop::parse(path) {
+                Ok(Some(session)) if !session.no_display => session,
+                _ => continue,
+            };
+            let entry = if x11 {
+                // startx only accepts a client given by its absolute path
+                (
+                    format!("{} (X11)", session.name),
+                    format!("startx /usr/bin/env {}", session.exec).into(),
+                )
+            } else {
+                (session.name, session.exec.into())
+            };
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Informational system status: battery, CPU load, and free disk space.
+
+use std::{fs, path::Path, process::Command};
+
+use anyhow::Result;
+
+use crate::config::{Entry, Menu};
+
+/// Generates an informational entry for each battery, the CPU load, and the free space on the
+/// root filesystem. Statuses that can't be read are left out.
+pub(super) fn entries(_menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let mut entries = batteries();
+    entries.extend(load());
+    entries.extend(disk_free());
+    Ok(entries
+        .into_iter()
+        .map(|(name, value)| {
+            (
+                name,
+                Entry {
+                    value,
+                    info: true,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect())
+}
+
+fn batteries() -> Vec<(String, String)> {
+    let read_dir = match fs::read_dir("/sys/class/power_supply") {
+        Ok(read_dir) => read_dir,
+        Err(_) => return Vec::new(),
+    };
+    let read = |path: &Path, file: &str| {
+        fs::read_to_string(path.join(file))
+            .map(|s| s.trim().to_string())
+            .ok()
+    };
+
+    read_dir
+        .flatten()
+        .filter_map(|item| {
+            let path = item.path();
+            if read(&path, "type")? != "Battery" {
+                return None;
+            }
+            let capacity = read(&path, "capacity")?;
+            let value = match read(&path, "status") {
+                Some(status) => format!("{capacity}% ({})", status.to_lowercase()),
+                None => format!("{capacity}%"),
+            };
+            let name = item.file_name().to_string_lossy().to_lowercase();
+            Some((format!("battery {name}"), value))
+        })
+        .collect()
+}
+
+fn load() -> Option<(String, String)> {
+    let loadavg = fs::read_to_string("/proc/loadavg").ok()?;
+    let averages: Vec<&str> = loadavg.split_whitespace().take(3).collect();
+    Some(("cpu load".to_string(), averages.join(" ")))
+}
+
+fn disk_free() -> Option<(String, String)> {
+    let output = Command::new("df").args(["-Pk", "/"]).output().ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Filesystem, 1024-blocks, Used, Available, Capacity, Mounted on
+    let fields: Vec<&str> = stdout.lines().nth(1)?.split_whitespace().collect();
+    let total: f64 = fields.get(1)?.parse().ok()?;
+    let available: f64 = fields.get(3)?.parse().ok()?;
+    let gib = |kib: f64| kib / 1024.0 / 1024.0;
+    Some((
+        "disk free".to_string(),
+        format!("{:.1}G / {:.1}G", gib(available), gib(total)),
+    ))
+}
@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Workspaces of the running compositor or window manager.
+
+use std::{env, process::Command};
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use crate::{
+    config::{Entry, Menu},
+    util::quote,
+};
+
+/// A workspace, as listed by the compositor.
+struct Workspace {
+    /// The number used to sort the workspaces.
+    order: i64,
+    name: String,
+    windows: usize,
+
+    /// The command that focuses the workspace.
+    focus: String,
+}
+
+/// Generates an entry for each workspace, described by its number of windows, that focuses the
+/// workspace when submitted. Hyprland and sway are detected from the environment variables they
+/// set, and any other window manager is queried with `wmctrl`.
+pub(super) fn entries(_menu: &Menu) -> Result<Vec<(String, Entry)>> {
+    let workspaces = if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        hyprland()?
+    } else if env::var_os("SWAYSOCK").is_some() {
+        sway()?
+    } else {
+        wmctrl()?
+    };
+    Ok(workspaces
+        .into_iter()
+        .map(|workspace| {
+            let desc = match workspace.windows {
+                1 => "1 window".to_string(),
+                n => format!("{n} windows"),
+            };
+            (
+                workspace.name,
+                Entry {
+                    order: workspace.order,
+                    value: workspace.focus,
+                    desc: Some(desc),
+                    ..Default::default()
+                },
+            )
+        })
+        .collect())
+}
+
+/// Runs a command and returns its output, failing if it exits unsuccessfully.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {program}"))?;
+    if !output.status.success() {
+        bail!("{program} exited with {}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn hyprland() -> Result<Vec<Workspace>> {
+    let json: Value = serde_json::from_str(&run("hyprctl", &["workspaces", "-j"])?)
+        .context("Failed to parse the workspaces listed by hyprctl")?;
+    Ok(json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|workspace| {
+            // Special workspaces have negative IDs, and can't be switched to
+            let id = workspace["id"].as_i64().filter(|&id| id > 0)?;
+            Some(Workspace {
+                order: id,
+                name: workspace["name"].as_str()?.to_string(),
+                windows: workspace["windows"].as_u64().unwrap_or_default() as usize,
+                focus: format!("hyprctl dispatch workspace {id}"),
+            })
+        })
+        .collect())
+}
+
+fn sway() -> Result<Vec<Workspace>> {
+    let tree: Value = serde_json::from_str(&run("swaymsg", &["-t", "get_tree", "-r"])?)
+        .context("Failed to parse the tree listed by swaymsg")?;
+
+    let mut workspaces = Vec::new();
+    let mut stack = vec![&tree];
+    while let Some(node) = stack.pop() {
+        if node["type"] == "workspace" {
+            let name = match node["name"].as_str() {
+                // The scratchpad is a hidden workspace
+                Some(name) if name != "__i3_scratch" => name,
+                _ => continue,
+            };
+            workspaces.push(Workspace {
+                order: node["num"].as_i64().unwrap_or(i64::MAX),
+                name: name.to_string(),
+                windows: count_sway_windows(node),
+                focus: format!("swaymsg workspace {}", quote(name)),
+            });
+        } else {
+            stack.extend(node["nodes"].as_array().into_iter().flatten());
+        }
+    }
+    Ok(workspaces)
+}
+
+/// Counts the windows in a sway node, which are the leaves of the tree, tiled or floating.
+fn count_sway_windows(node: &Value) -> usize {
+    let children: Vec<&Value> = ["nodes", "floating_nodes"]
+        .iter()
+        .flat_map(|key| node[key].as_array().into_iter().flatten())
+        .collect();
+    if children.is_empty() {
+        usize::from(node["type"] == "con" || node["type"] == "floating_con")
+    } else {
+        children.into_iter().map(count_sway_windows).sum()
+    }
+}
+
+fn wmctrl() -> Result<Vec<Workspace>> {
+    let desktops = run("wmctrl", &["-d"])?;
+    let windows = run("wmctrl", &["-l"])?;
+
+    // Windows are listed as `id desktop host title`, where the desktop is -1 for sticky windows
+    let window_desktops: Vec<i64> = windows
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1)?.parse().ok())
+        .collect();
+
+    // Desktops are listed as `index current geometry... name`, where the name is last
+    Ok(desktops
+        .lines()
+        .filter_map(|line| {
+            let index: i64 = line.split_whitespace().next()?.parse().ok()?;
+            let name = line
+                .split_once("  WA: ")
+                .and_then(|(_, rest)| rest.split_once("  "))
+                .map(|(_, name)| name.trim())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| (index + 1).to_string());
+            Some(Workspace {
+                order: index,
+                name,
+                windows: window_desktops.iter().filter(|&&d| d == index).count(),
+                focus: format!("wmctrl -s {index}"),
+            })
+        })
+        .collect())
+}
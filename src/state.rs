@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use crate::{frame::Frame, options::MatchMode};
+
 /// Indicates the next action the program should take.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub(crate) enum Action {
     /// Indicates that the program should continue.
+    #[default]
     None,
 
     /// Indicates that the program should exit without submitting.
@@ -14,12 +17,34 @@ pub(crate) enum Action {
 
     /// Indicates that the program should submit the selected entry and exit.
     Submit,
-}
 
-impl Default for Action {
-    fn default() -> Self {
-        Action::None
-    }
+    /// Indicates that the selected entry, or the input if nothing matches it, should be added to
+    /// a menu in the user's config file.
+    Promote,
+
+    /// Indicates that the match mode should be switched to the next one.
+    MatchMode,
+
+    /// Indicates that the selected entry should be disabled in the user's config file.
+    Disable,
+
+    /// Indicates that entry values should be shown if they're hidden, or hidden if they're shown.
+    ToggleValues,
+
+    /// Indicates that the clipboard should be inserted into the input at the cursor.
+    Paste,
+
+    /// Indicates that the selected entry of the recent menu should be removed from the history.
+    Forget,
+
+    /// Indicates that the value of the selected entry should be copied to the clipboard.
+    Yank,
+
+    /// Indicates that the input should be replaced with the name of the selected entry.
+    Complete,
+
+    /// Indicates that the config should be read again.
+    Reload,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -30,8 +55,9 @@ pub(crate) struct State {
     /// Indicates the next action the program should take.
     pub(crate) action: Action,
 
-    /// Position of the input cursor, offset from the left.
-    pub(crate) cursor_x: u16,
+    /// Position of the input cursor, as a byte offset into the input that's always on a char
+    /// boundary.
+    pub(crate) cursor_x: usize,
 
     /// Indicates that the entry cursor is visible.
     pub(crate) entry_cursor: bool,
@@ -42,9 +68,415 @@ pub(crate) struct State {
     /// Index of the selected entry.
     pub(crate) entry_index: usize,
 
-    /// The number of menus in the config.
+    /// The number of rows that entries can be drawn in, i.e. a page of entries.
+    pub(crate) entry_rows: usize,
+
+    /// The number of entries side by side in each row.
+    pub(crate) entry_columns: usize,
+
+    /// Index of the first visible row of entries.
+    pub(crate) entry_scroll: usize,
+
+    /// The number of menus shown as tabs. Submenus come after them.
     pub(crate) menu_count: usize,
 
     /// Index of the current menu.
     pub(crate) menu_index: usize,
+
+    /// Indices of the menus that the current submenu was opened from, outermost first.
+    pub(crate) menu_stack: Vec<usize>,
+
+    /// Rows at the bottom of the interface reserved for the scrollback.
+    pub(crate) scrollback_rows: usize,
+
+    /// Columns at the right of the entries reserved for the preview pane.
+    pub(crate) preview_width: u16,
+
+    /// Rows below the entries reserved for the preview pane.
+    pub(crate) preview_rows: usize,
+
+    /// The area that the interface is drawn in, fitted to the terminal before each frame is
+    /// drawn.
+    pub(crate) frame: Frame,
+
+    /// The output of the preview command for the selected entry, once it has finished.
+    pub(crate) preview_output: Option<Vec<String>>,
+
+    /// The match mode chosen with the match mode keybind, which overrides the config.
+    pub(crate) match_mode: Option<MatchMode>,
+
+    /// Whether entry values are shown, as toggled with the toggle values keybind, which overrides
+    /// the config.
+    pub(crate) show_values: Option<bool>,
+
+    /// Indicates that the next key press is described instead of handled.
+    pub(crate) describing_key: bool,
+
+    /// Indicates that the list of keybinds is shown over the entries, until the next key press.
+    pub(crate) help: bool,
+
+    /// What the last key press described with the describe key keybind does.
+    pub(crate) key_description: Option<String>,
+
+    /// Indicates that keys navigate instead of typing, with the `vim_mode` option.
+    pub(crate) normal_mode: bool,
+
+    /// The first key of a two-key command in normal mode, e.g. the first `g` of `gg`.
+    pub(crate) pending_key: Option<char>,
+
+    /// The name of the custom action that the selected entry is being submitted with, if it
+    /// wasn't submitted with the submit keybind.
+    pub(crate) custom_action: Option<String>,
+}
+
+impl State {
+    /// Backs out one level of the interface: hides the entry cursor, or if it's already hidden,
+    /// clears the input, or if it's already empty, leaves the current submenu. Returns `false` if
+    /// there's nothing left to back out of.
+    pub(crate) fn back_out(&mut self) -> bool {
+        if self.entry_cursor {
+            self.entry_cursor = false;
+            self.entry_index = 0;
+        } else if !self.input.is_empty() {
+            self.input.clear();
+            self.cursor_x = 0;
+        } else {
+            return self.leave_submenu();
+        }
+        true
+    }
+
+    /// Returns the position of the input cursor, as a byte offset into the input.
+    pub(crate) fn cursor(&self) -> usize {
+        self.cursor_x.min(self.input.len())
+    }
+
+    /// Moves the input cursor to a byte offset into the input, which must be on a char boundary.
+    pub(crate) fn set_cursor(&mut self, cursor: usize) {
+        debug_assert!(self.input.is_char_boundary(cursor));
+        self.cursor_x = cursor;
+    }
+
+    /// Returns the position in the input of the char after the cursor.
+    pub(crate) fn char_next(&self) -> usize {
+        let cursor = self.cursor();
+        self.input[cursor..]
+            .chars()
+            .next()
+            .map_or(cursor, |c| cursor + c.len_utf8())
+    }
+
+    /// Returns the position in the input of the char before the cursor.
+    pub(crate) fn char_back(&self) -> usize {
+        let cursor = self.cursor();
+        self.input[..cursor]
+            .chars()
+            .next_back()
+            .map_or(cursor, |c| cursor - c.len_utf8())
+    }
+
+    /// Inserts text into the input at the cursor, as if it was typed, with line breaks and other
+    /// control characters replaced by spaces.
+    pub(crate) fn insert_input(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .collect();
+        let cursor = self.cursor();
+        self.input.insert_str(cursor, &text);
+        self.set_cursor(cursor + text.len());
+        self.input_changed();
+    }
+
+    /// Returns the position in the input of the start of the word before the cursor, skipping any
+    /// whitespace in between. Words are delimited by whitespace.
+    pub(crate) fn word_back(&self) -> usize {
+        let before = self.input[..self.cursor()].trim_end();
+        before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    /// Returns the position in the input of the end of the word after the cursor, skipping any
+    /// whitespace in between. Words are delimited by whitespace.
+    pub(crate) fn word_next(&self) -> usize {
+        let cursor = self.cursor();
+        let after = &self.input[cursor..];
+        let start = after.len() - after.trim_start().len();
+        let end = after[start..]
+            .find(char::is_whitespace)
+            .map_or(after.len(), |i| start + i);
+        cursor + end
+    }
+
+    /// Deletes the char after the input cursor.
+    pub(crate) fn delete_next(&mut self) {
+        let cursor = self.cursor();
+        self.input.replace_range(cursor..self.char_next(), "");
+        self.input_changed();
+    }
+
+    /// Deletes the char before the input cursor.
+    pub(crate) fn delete_back(&mut self) {
+        let start = self.char_back();
+        self.input.replace_range(start..self.cursor(), "");
+        self.set_cursor(start);
+        self.input_changed();
+    }
+
+    /// Deletes the word before the input cursor, along with any whitespace between it and the
+    /// cursor.
+    pub(crate) fn delete_word_back(&mut self) {
+        let start = self.word_back();
+        self.input.replace_range(start..self.cursor(), "");
+        self.set_cursor(start);
+        self.input_changed();
+    }
+
+    /// Deletes the input after the cursor.
+    pub(crate) fn delete_to_end(&mut self) {
+        self.input.truncate(self.cursor());
+        self.input_changed();
+    }
+
+    /// Deletes the input before the cursor.
+    pub(crate) fn delete_to_start(&mut self) {
+        self.input.replace_range(..self.cursor(), "");
+        self.cursor_x = 0;
+        self.input_changed();
+    }
+
+    /// Replaces the input with text, e.g. the name of an entry, with the cursor at its end.
+    pub(crate) fn replace_input(&mut self, text: &str) {
+        self.input = text.to_string();
+        self.set_cursor(self.input.len());
+        self.input_changed();
+    }
+
+    /// Hides the entry cursor after the input changed, since the matching entries changed too.
+    fn input_changed(&mut self) {
+        self.entry_cursor = false;
+        self.entry_index = 0;
+    }
+
+    /// Opens the menu at `menu_index` as a submenu of the current menu.
+    pub(crate) fn enter_submenu(&mut self, menu_index: usize) {
+        self.menu_stack.push(self.menu_index);
+        self.menu_index = menu_index;
+        self.reset_menu();
+    }
+
+    /// Returns to the menu that the current submenu was opened from. Returns `false` if the
+    /// current menu isn't a submenu.
+    pub(crate) fn leave_submenu(&mut self) -> bool {
+        match self.menu_stack.pop() {
+            Some(menu_index) => {
+                self.menu_index = menu_index;
+                self.reset_menu();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns to the submenu that was opened `depth` levels below a menu tab, leaving the
+    /// submenus opened from it. Nothing happens if the current menu isn't that deep.
+    pub(crate) fn leave_to(&mut self, depth: usize) {
+        while self.menu_stack.len() > depth {
+            self.leave_submenu();
+        }
+    }
+
+    /// Switches to the menu tab at `menu_index`, leaving any open submenus.
+    pub(crate) fn switch_tab(&mut self, menu_index: usize) {
+        self.menu_stack.clear();
+        self.menu_index = menu_index;
+        self.reset_menu();
+    }
+
+    /// Returns the index of the tab that the current menu was opened from, or of the current menu
+    /// if it isn't a submenu.
+    pub(crate) fn tab_index(&self) -> usize {
+        self.menu_stack.first().copied().unwrap_or(self.menu_index)
+    }
+
+    /// Clears the input and entry cursor, as when switching menus.
+    fn reset_menu(&mut self) {
+        self.input.clear();
+        self.cursor_x = 0;
+        self.entry_cursor = false;
+        self.entry_index = 0;
+        self.entry_scroll = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a state with the provided input and the cursor at the byte offset.
+    fn input(input: &str, cursor: usize) -> State {
+        let mut state = State {
+            input: input.to_string(),
+            ..Default::default()
+        };
+        state.set_cursor(cursor);
+        state
+    }
+
+    #[test]
+    fn insert_input_at_cursor() {
+        let mut state = input("ac", 1);
+        state.insert_input("b");
+        assert_eq!(state.input, "abc");
+        assert_eq!(state.cursor(), 2);
+    }
+
+    #[test]
+    fn cursor_past_u16_max() {
+        // 80000 bytes, so that a cursor stored in a u16 would land inside a char
+        let long = "é".repeat(40_000);
+        let mut state = State::default();
+        state.insert_input(&long);
+        assert_eq!(state.cursor(), long.len());
+
+        state.set_cursor(state.char_back());
+        assert_eq!(state.cursor(), long.len() - 2);
+        state.insert_input("x");
+        assert_eq!(state.cursor(), long.len() - 1);
+        assert_eq!(state.char_next(), long.len() + 1);
+        assert!(state.input.ends_with("xé"));
+    }
+
+    #[test]
+    fn insert_input_multibyte() {
+        let mut state = State::default();
+        state.insert_input("日");
+        state.insert_input("本");
+        assert_eq!(state.input, "日本");
+        assert_eq!(state.cursor(), "日本".len());
+
+        state.set_cursor(state.char_back());
+        state.insert_input("é");
+        assert_eq!(state.input, "日é本");
+        assert_eq!(state.cursor(), "日é".len());
+    }
+
+    #[test]
+    fn insert_input_replaces_control_chars() {
+        let mut state = State::default();
+        state.insert_input("a\tb\n");
+        assert_eq!(state.input, "a b");
+    }
+
+    #[test]
+    fn char_next_and_back_step_over_multibyte_chars() {
+        let state = input("aé日", 1);
+        assert_eq!(state.char_next(), 3);
+        assert_eq!(state.char_back(), 0);
+        let state = input("aé日", 6);
+        assert_eq!(state.char_next(), 6);
+        assert_eq!(state.char_back(), 3);
+    }
+
+    #[test]
+    fn delete_next_and_back_multibyte() {
+        let mut state = input("aé日", 3);
+        state.delete_back();
+        assert_eq!(state.input, "a日");
+        assert_eq!(state.cursor(), 1);
+        state.delete_next();
+        assert_eq!(state.input, "a");
+        assert_eq!(state.cursor(), 1);
+    }
+
+    #[test]
+    fn word_back_and_next() {
+        let state = input("foo bar  baz", 9);
+        assert_eq!(state.word_back(), 4);
+        assert_eq!(state.word_next(), 12);
+        let state = input("foo bar", 0);
+        assert_eq!(state.word_back(), 0);
+        assert_eq!(state.word_next(), 3);
+    }
+
+    #[test]
+    fn delete_word_back() {
+        let mut state = input("foo bar  ", 9);
+        state.delete_word_back();
+        assert_eq!(state.input, "foo ");
+        assert_eq!(state.cursor(), 4);
+        state.delete_word_back();
+        assert_eq!(state.input, "");
+        assert_eq!(state.cursor(), 0);
+    }
+
+    #[test]
+    fn delete_word_back_multibyte() {
+        let mut state = input("ab é", "ab é".len());
+        state.delete_word_back();
+        assert_eq!(state.input, "ab ");
+        assert_eq!(state.cursor(), 3);
+
+        let mut state = input("日本 語x", "日本 語".len());
+        state.delete_word_back();
+        assert_eq!(state.input, "日本 x");
+        assert_eq!(state.cursor(), "日本 ".len());
+    }
+
+    #[test]
+    fn delete_word_back_hides_entry_cursor() {
+        let mut state = State {
+            entry_cursor: true,
+            entry_index: 3,
+            ..input("foo", 3)
+        };
+        state.delete_word_back();
+        assert!(!state.entry_cursor);
+        assert_eq!(state.entry_index, 0);
+    }
+
+    #[test]
+    fn delete_to_end_and_start_multibyte() {
+        let mut state = input("日本語", 3);
+        state.delete_to_end();
+        assert_eq!(state.input, "日");
+        assert_eq!(state.cursor(), 3);
+
+        let mut state = input("日本語", 6);
+        state.delete_to_start();
+        assert_eq!(state.input, "語");
+        assert_eq!(state.cursor(), 0);
+    }
+
+    #[test]
+    fn replace_input_moves_cursor_to_end() {
+        let mut state = input("fi", 1);
+        state.replace_input("Firefox 日本");
+        assert_eq!(state.cursor(), "Firefox 日本".len());
+    }
+
+    #[test]
+    fn leave_to_returns_to_an_ancestor() {
+        let mut state = State::default();
+        for submenu in [2, 3, 4] {
+            state.enter_submenu(submenu);
+        }
+        assert_eq!(state.menu_stack, [0, 2, 3]);
+        state.leave_to(3);
+        assert_eq!(state.menu_index, 4);
+        state.leave_to(1);
+        assert_eq!(
+            (state.menu_index, state.menu_stack.as_slice()),
+            (2, &[0][..])
+        );
+        state.leave_to(0);
+        assert_eq!(
+            (state.menu_index, state.menu_stack.as_slice()),
+            (0, &[][..])
+        );
+    }
 }
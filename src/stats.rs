@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Exports usage statistics from the history.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    config,
+    history::{self, Record},
+    util,
+};
+
+/// The format that statistics are exported in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum Format {
+    /// Submission counts per menu, per entry, and per day.
+    #[default]
+    Json,
+
+    /// One row per entry and day, with the number of submissions.
+    Csv,
+}
+
+/// Usage statistics, exported as JSON.
+#[derive(Debug, Default, Serialize)]
+struct Stats {
+    /// The total number of submissions.
+    total: usize,
+
+    /// Submissions per menu, most used first.
+    menus: Vec<MenuStats>,
+
+    /// Submissions per entry, most used first.
+    entries: Vec<EntryStats>,
+
+    /// Submissions per day, oldest first.
+    days: Vec<DayStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct MenuStats {
+    menu: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct EntryStats {
+    menu: String,
+    entry: String,
+    count: usize,
+    /// When the entry was last submitted, in seconds since the Unix epoch.
+    last_used: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DayStats {
+    /// The date in UTC, as `YYYY-MM-DD`.
+    day: String,
+    count: usize,
+}
+
+/// Prints usage statistics from the history. If a config path is provided, or a config exists
+/// in the default locations, only the menus defined in it are included.
+pub(crate) fn stats(path: Option<PathBuf>, format: Format) -> Result<()> {
+    let menus = match config::config_path(path.clone()) {
+        Ok(path) => Some(menu_names(path)?),
+        // A missing config is only an error if one was asked for
+        Err(e) if path.is_some() => return Err(e),
+        Err(_) => None,
+    };
+    let records: Vec<Record> = history::read()
+        .into_iter()
+        .filter(|record| {
+            menus
+                .as_ref()
+                .is_none_or(|menus| menus.contains(&record.menu))
+        })
+        .collect();
+
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&collect(&records))?),
+        Format::Csv => print!("{}", csv(&records)),
+    }
+    Ok(())
+}
+
+/// Returns the names of the menus in the config, without generating their entries.
+fn menu_names(path: PathBuf) -> Result<HashSet<String>> {
+    let raw = config::read_config(Some(path), None)?;
+    Ok(raw
+        .get_table("menus")
+        .unwrap_or_default()
+        .into_keys()
+        .collect())
+}
+
+/// Aggregates submissions by menu, entry, and day.
+fn collect(records: &[Record]) -> Stats {
+    let mut menus: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut entries: BTreeMap<(&str, &str), (usize, u64)> = BTreeMap::new();
+    let mut days: BTreeMap<String, usize> = BTreeMap::new();
+
+    for record in records {
+        *menus.entry(&record.menu).or_default() += 1;
+        let entry = entries.entry((&record.menu, &record.entry)).or_default();
+        entry.0 += 1;
+        entry.1 = entry.1.max(record.time);
+        *days.entry(day(record.time)).or_default() += 1;
+    }
+
+    let mut stats = Stats {
+        total: records.len(),
+        menus: menus
+            .into_iter()
+            .map(|(menu, count)| MenuStats {
+                menu: menu.to_string(),
+                count,
+            })
+            .collect(),
+        entries: entries
+            .into_iter()
+            .map(|((menu, entry), (count, last_used))| EntryStats {
+                menu: menu.to_string(),
+                entry: entry.to_string(),
+                count,
+                last_used,
+            })
+            .collect(),
+        days: days
+            .into_iter()
+            .map(|(day, count)| DayStats { day, count })
+            .collect(),
+    };
+    // Stable sorts keep ties in alphabetical order
+    stats.menus.sort_by_key(|menu| Reverse(menu.count));
+    stats.entries.sort_by_key(|entry| Reverse(entry.count));
+    stats
+}
+
+/// Formats submissions as CSV with the columns `day,menu,entry,count`.
+fn csv(records: &[Record]) -> String {
+    let mut rows: BTreeMap<(String, &str, &str), usize> = BTreeMap::new();
+    for record in records {
+        *rows
+            .entry((day(record.time), &record.menu, &record.entry))
+            .or_default() += 1;
+    }
+
+    let mut csv = String::from("day,menu,entry,count\n");
+    for ((day, menu, entry), count) in rows {
+        csv += &format!("{day},{},{},{count}\n", csv_field(menu), csv_field(entry));
+    }
+    csv
+}
+
+/// Quotes a CSV field if it contains a comma, quote, or line break.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats a Unix timestamp as a UTC date.
+fn day(time: u64) -> String {
+    let (year, month, day) = util::civil_from_days((time / 86400) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Runs the `status_style_cmd` of entries, whose exit status selects the style of their values.
+
+use std::{
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::config::Menu;
+
+/// How long the status commands may run before they're killed and treated as failed, so that a
+/// hung command doesn't keep the menu from opening.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often the status commands are checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The status of an entry, from the exit status of its `status_style_cmd`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Status {
+    /// The command exited with status 0. The value is drawn in the `entry_value_ok` style.
+    Ok,
+
+    /// The command exited with status 1. The value is drawn in the `entry_value_warn` style.
+    Warn,
+
+    /// The command exited with any other status, couldn't be run, or timed out. The value is drawn
+    /// in the `entry_value_err` style.
+    Err,
+}
+
+/// Runs the status commands of every entry in the menus at once, and sets the entries' statuses
+/// once they exit.
+pub(crate) fn probe<'a>(menus: impl IntoIterator<Item = &'a mut Menu>) {
+    // Each command's process, until it exits
+    let mut probes: Vec<(&mut Option<Status>, Option<Child>)> = Vec::new();
+    for menu in menus {
+        for (_, entry) in menu.entries.iter_mut() {
+            let Some(command) = &entry.status_style_cmd else {
+                continue;
+            };
+            let spawned = Command::new("/bin/sh")
+                .arg("-c")
+                .arg(command)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            match spawned {
+                Ok(child) => probes.push((&mut entry.status, Some(child))),
+                Err(_) => entry.status = Some(Status::Err),
+            }
+        }
+    }
+
+    let start = Instant::now();
+    loop {
+        let mut running = false;
+        for (status, child) in probes.iter_mut() {
+            let Some(process) = child else {
+                continue;
+            };
+            **status = match process.try_wait() {
+                Ok(None) => {
+                    running = true;
+                    continue;
+                }
+                Ok(Some(exit)) => match exit.code() {
+                    Some(0) => Some(Status::Ok),
+                    Some(1) => Some(Status::Warn),
+                    _ => Some(Status::Err),
+                },
+                Err(_) => Some(Status::Err),
+            };
+            *child = None;
+        }
+        if !running {
+            return;
+        }
+        if start.elapsed() >= TIMEOUT {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    // Reap the commands that timed out so they don't linger as zombies
+    for (status, child) in probes.iter_mut() {
+        if let Some(process) = child {
+            let _ = process.kill();
+            let _ = process.wait();
+            **status = Some(Status::Err);
+        }
+    }
+}
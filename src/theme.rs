@@ -3,9 +3,19 @@
 //!
 //! See [Theme] to view the accepted fields in a theme configuration.
 
-use std::fmt;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock,
+    },
+};
 
-use crossterm::style::Attribute;
+use crossterm::{
+    style::{Attribute, Attributes, Color},
+    Command,
+};
 use serde::{
     de::{self, Unexpected, Visitor},
     Deserialize, Deserializer,
@@ -32,27 +42,67 @@ impl<'de> Deserialize<'de> for ThemeColor {
             type Value = ThemeColor;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a valid CSS color")
+                formatter.write_str("a valid CSS color, ANSI color name, ansi(0-255), or default")
             }
 
             fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
             where
                 E: de::Error,
             {
-                let css_color = csscolorparser::parse(s)
-                    .map_err(|_| de::Error::invalid_value(Unexpected::Str(s), &self))?;
-                let crossterm_color = crossterm::style::Color::Rgb {
-                    r: (css_color.r * 255.0) as u8,
-                    g: (css_color.g * 255.0) as u8,
-                    b: (css_color.b * 255.0) as u8,
-                };
-                Ok(ThemeColor(crossterm_color))
+                parse_color(s)
+                    .map(ThemeColor)
+                    .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(s), &self))
             }
         }
         deserializer.deserialize_str(ThemeColorVisitor)
     }
 }
 
+/// The names of the 16 ANSI colors, which are drawn with the terminal's own palette.
+const ANSI_COLORS: &[(&str, Color)] = &[
+    ("black", Color::Black),
+    ("red", Color::DarkRed),
+    ("green", Color::DarkGreen),
+    ("yellow", Color::DarkYellow),
+    ("blue", Color::DarkBlue),
+    ("magenta", Color::DarkMagenta),
+    ("cyan", Color::DarkCyan),
+    ("white", Color::Grey),
+    ("brightblack", Color::DarkGrey),
+    ("brightred", Color::Red),
+    ("brightgreen", Color::Green),
+    ("brightyellow", Color::Yellow),
+    ("brightblue", Color::Blue),
+    ("brightmagenta", Color::Magenta),
+    ("brightcyan", Color::Cyan),
+    ("brightwhite", Color::White),
+];
+
+/// Parses an ANSI color name, e.g. `red` or `brightblue`, an index into the terminal's 256-color
+/// palette, e.g. `ansi(208)`, or any valid CSS color format into a crossterm color. `default` and
+/// `none` are the terminal's own foreground or background color.
+pub(crate) fn parse_color(s: &str) -> Option<crossterm::style::Color> {
+    let name = s.trim().to_lowercase();
+    if name == "default" || name == "none" {
+        return Some(Color::Reset);
+    }
+    if let Some((_, color)) = ANSI_COLORS.iter().find(|(ansi, _)| *ansi == name) {
+        return Some(*color);
+    }
+    if let Some(index) = name
+        .strip_prefix("ansi(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        return index.trim().parse().ok().map(Color::AnsiValue);
+    }
+    let css_color = csscolorparser::parse(s).ok()?;
+    Some(crossterm::style::Color::Rgb {
+        r: (css_color.r * 255.0) as u8,
+        g: (css_color.g * 255.0) as u8,
+        b: (css_color.b * 255.0) as u8,
+    })
+}
+
 /// Used to deserialize a comma seperated list of text attributes.
 #[derive(Debug, Default)]
 pub(crate) struct ThemeAttributes(pub(crate) crossterm::style::Attributes);
@@ -77,16 +127,10 @@ impl<'de> Deserialize<'de> for ThemeAttributes {
             {
                 let attrs_vec: Vec<Attribute> = s
                     .split(',')
-                    .map(|a| match a.trim() {
-                        "bold" => Ok(Attribute::Bold),
-                        "dim" => Ok(Attribute::Dim),
-                        "italic" => Ok(Attribute::Italic),
-                        "underlined" => Ok(Attribute::Underlined),
-                        "hidden" => Ok(Attribute::Hidden),
-                        _ => Err(de::Error::custom(format!(
-                            "invalid attribute '{}'",
-                            a.trim()
-                        ))),
+                    .map(|a| {
+                        parse_attribute(a.trim()).ok_or_else(|| {
+                            de::Error::custom(format!("invalid attribute '{}'", a.trim()))
+                        })
                     })
                     .collect::<Result<_, E>>()?;
                 let attrs = crossterm::style::Attributes::from(attrs_vec.as_slice());
@@ -97,6 +141,98 @@ impl<'de> Deserialize<'de> for ThemeAttributes {
     }
 }
 
+/// Parses the name of a text attribute.
+fn parse_attribute(name: &str) -> Option<Attribute> {
+    match name {
+        "bold" => Some(Attribute::Bold),
+        "dim" => Some(Attribute::Dim),
+        "italic" => Some(Attribute::Italic),
+        "underlined" => Some(Attribute::Underlined),
+        "doubleunderlined" => Some(Attribute::DoubleUnderlined),
+        "undercurled" => Some(Attribute::Undercurled),
+        "underdotted" => Some(Attribute::Underdotted),
+        "underdashed" => Some(Attribute::Underdashed),
+        "overlined" => Some(Attribute::OverLined),
+        "strikethrough" => Some(Attribute::CrossedOut),
+        "reverse" => Some(Attribute::Reverse),
+        "blink" => Some(Attribute::SlowBlink),
+        "rapidblink" => Some(Attribute::RapidBlink),
+        "framed" => Some(Attribute::Framed),
+        "encircled" => Some(Attribute::Encircled),
+        "hidden" => Some(Attribute::Hidden),
+        _ => None,
+    }
+}
+
+/// Text attributes that are drawn as another attribute, or as no attribute (`None`), for
+/// terminals that render them poorly.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "HashMap<String, String>")]
+pub(crate) struct AttributeFallbacks(Vec<(Attribute, Option<Attribute>)>);
+
+impl TryFrom<HashMap<String, String>> for AttributeFallbacks {
+    type Error = String;
+
+    fn try_from(map: HashMap<String, String>) -> Result<Self, Self::Error> {
+        map.iter()
+            .map(|(from, to)| {
+                let from = parse_attribute(from).ok_or(format!("invalid attribute '{from}'"))?;
+                let to = match to.as_str() {
+                    "none" => None,
+                    _ => Some(parse_attribute(to).ok_or(format!("invalid attribute '{to}'"))?),
+                };
+                Ok((from, to))
+            })
+            .collect::<Result<_, _>>()
+            .map(AttributeFallbacks)
+    }
+}
+
+/// The attribute fallbacks that every style is drawn with, set from the `attr_fallbacks` option.
+static FALLBACKS: RwLock<AttributeFallbacks> = RwLock::new(AttributeFallbacks(Vec::new()));
+
+/// Sets the attribute fallbacks that [attributes] applies.
+pub(crate) fn set_fallbacks(fallbacks: &AttributeFallbacks) {
+    if let Ok(mut current) = FALLBACKS.write() {
+        *current = fallbacks.clone();
+    }
+}
+
+/// Returns the attributes to draw a style with, after applying the attribute fallbacks. Used by
+/// [set_style](crate::set_style).
+pub(crate) fn attributes(attrs: &ThemeAttributes) -> Attributes {
+    let Ok(fallbacks) = FALLBACKS.read() else {
+        return attrs.0;
+    };
+    // Fallbacks aren't chained, so that e.g. swapping two attributes works
+    let mut result = attrs.0;
+    for (from, _) in &fallbacks.0 {
+        if attrs.0.has(*from) {
+            result.unset(*from);
+        }
+    }
+    for (from, to) in &fallbacks.0 {
+        if let (true, Some(to)) = (attrs.0.has(*from), to) {
+            result.set(*to);
+        }
+    }
+    result
+}
+
+/// Indicates that colors aren't drawn, only text attributes, set with `NO_COLOR` or `--no-color`.
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether colors are drawn.
+pub(crate) fn set_no_color(no_color: bool) {
+    NO_COLOR.store(no_color, Ordering::Relaxed);
+}
+
+/// Returns the color to draw a style's color with, or `None` if colors aren't drawn. Used by
+/// [set_style](crate::set_style).
+pub(crate) fn color(color: &ThemeColor) -> Option<Color> {
+    (!NO_COLOR.load(Ordering::Relaxed)).then_some(color.0)
+}
+
 /// A text style.
 #[derive(Debug, Deserialize, Default)]
 pub(crate) struct ThemeStyle {
@@ -111,6 +247,10 @@ pub(crate) struct ThemeStyle {
     /// Text attributes.
     #[serde(default)]
     pub(crate) attrs: ThemeAttributes,
+
+    /// Color of the underline, if the attributes include one. Defaults to the foreground color.
+    #[serde(default)]
+    pub(crate) underline_color: Option<ThemeColor>,
 }
 
 /// A collection of styles to be used in the interface.
@@ -131,9 +271,36 @@ pub(crate) struct Theme {
     /// Style for the value (right side) of a menu entry.
     pub(crate) entry_value: ThemeStyle,
 
+    /// Style for entry values whose `status_style_cmd` exited with status 0.
+    pub(crate) entry_value_ok: ThemeStyle,
+
+    /// Style for entry values whose `status_style_cmd` exited with status 1.
+    pub(crate) entry_value_warn: ThemeStyle,
+
+    /// Style for entry values whose `status_style_cmd` failed.
+    pub(crate) entry_value_err: ThemeStyle,
+
+    /// Style for the description (middle) of a menu entry.
+    pub(crate) entry_desc: ThemeStyle,
+
     /// Style for letters that match the user's input.
     pub(crate) entry_match: ThemeStyle,
 
+    /// Style for informational entries, which can't be submitted.
+    pub(crate) entry_info: ThemeStyle,
+
+    /// Style for separator entries.
+    pub(crate) entry_separator: ThemeStyle,
+
+    /// Style for label entries.
+    pub(crate) entry_label: ThemeStyle,
+
+    /// Style for the keys of entries in hotkey menus.
+    pub(crate) entry_key: ThemeStyle,
+
+    /// Style for entry icons.
+    pub(crate) entry_icon: ThemeStyle,
+
     /// Style for entries that do not match the user's input.
     pub(crate) entry_hidden: ThemeStyle,
 
@@ -143,9 +310,112 @@ pub(crate) struct Theme {
     /// Style for letters that match the user's input in the selected entry.
     pub(crate) entry_cursor_match: ThemeStyle,
 
+    /// Style for the position of the selected entry among the matches, at the end of the prompt
+    /// line.
+    pub(crate) entry_position: ThemeStyle,
+
+    /// Style for the number of matching entries after the input, with the `match_count` option.
+    pub(crate) match_count: ThemeStyle,
+
+    /// Style for executions in the scrollback that are still running.
+    pub(crate) scrollback_running: ThemeStyle,
+
+    /// Style for executions in the scrollback that succeeded.
+    pub(crate) scrollback_success: ThemeStyle,
+
+    /// Style for executions in the scrollback that failed.
+    pub(crate) scrollback_failure: ThemeStyle,
+
     /// Style for menu names (i.e. tabs) that are not selected.
     pub(crate) menu_name: ThemeStyle,
 
     /// Style for the selected menu name.
     pub(crate) menu_cursor: ThemeStyle,
+
+    /// Style for the separators between menu names.
+    pub(crate) menu_separator: ThemeStyle,
+
+    /// Style for the trail of open submenus after the menu names.
+    pub(crate) menu_breadcrumb: ThemeStyle,
+
+    /// Style for the markers of menu names that are scrolled out of view on the menu line.
+    pub(crate) menu_overflow: ThemeStyle,
+
+    /// Style for the match mode at the end of the menu line, shown unless it's fuzzy.
+    pub(crate) match_mode: ThemeStyle,
+
+    /// Style for the value preview under the prompt.
+    pub(crate) value_preview: ThemeStyle,
+
+    /// Style for the description of a key press under the prompt.
+    pub(crate) key_description: ThemeStyle,
+
+    /// Style for the message under the prompt, given with `--mesg`.
+    pub(crate) message: ThemeStyle,
+
+    /// Style for the text in the preview pane.
+    pub(crate) preview: ThemeStyle,
+
+    /// Style for the border between the entries and the preview pane.
+    pub(crate) preview_border: ThemeStyle,
+
+    /// Style for the border of the box that the interface is drawn in.
+    pub(crate) box_border: ThemeStyle,
+
+    /// Style for the header line, above the menu line.
+    pub(crate) header: ThemeStyle,
+
+    /// Style for the footer line, in the bottom row.
+    pub(crate) footer: ThemeStyle,
+
+    /// Appearance of the input cursor.
+    #[serde(default)]
+    pub(crate) cursor: CursorStyle,
+}
+
+/// The shape of the input cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CursorShape {
+    /// The terminal's own cursor, which is left alone.
+    #[default]
+    Default,
+
+    /// A block over the character after the cursor.
+    Block,
+
+    /// A vertical bar between characters.
+    Bar,
+
+    /// A line under the character after the cursor.
+    Underline,
+}
+
+/// The appearance of the input cursor, set by `cursor` in the theme. Queued as a command, it sets
+/// the cursor's shape; the default style restores the terminal's own cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub(crate) struct CursorStyle {
+    /// The shape of the cursor.
+    #[serde(default)]
+    pub(crate) shape: CursorShape,
+
+    /// Indicates that the cursor blinks. Has no effect on the terminal's own cursor.
+    #[serde(default)]
+    pub(crate) blink: bool,
+}
+
+impl Command for CursorStyle {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // DECSCUSR, which crossterm only supports for steady block cursors
+        let ps = match (self.shape, self.blink) {
+            (CursorShape::Default, _) => 0,
+            (CursorShape::Block, true) => 1,
+            (CursorShape::Block, false) => 2,
+            (CursorShape::Underline, true) => 3,
+            (CursorShape::Underline, false) => 4,
+            (CursorShape::Bar, true) => 5,
+            (CursorShape::Bar, false) => 6,
+        };
+        write!(f, "\x1b[{ps} q")
+    }
 }
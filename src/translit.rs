@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Transliterates entry names into Latin letters, so that they can be matched by typing in Latin.
+
+/// A transliterated name, along with the index of the original character that each of its
+/// characters came from.
+pub(crate) struct Transliteration {
+    pub(crate) text: String,
+    origins: Vec<usize>,
+}
+
+impl Transliteration {
+    /// Maps indices of characters in the transliterated text back to the original text.
+    pub(crate) fn origins(&self, indices: &[usize]) -> Vec<usize> {
+        let mut origins: Vec<usize> = indices
+            .iter()
+            .filter_map(|&i| self.origins.get(i).copied())
+            .collect();
+        origins.dedup();
+        origins
+    }
+}
+
+/// Transliterates a name, or returns `None` if it's already ASCII and would be unchanged.
+#[cfg(feature = "transliteration")]
+pub(crate) fn transliterate(name: &str) -> Option<Transliteration> {
+    if name.is_ascii() {
+        return None;
+    }
+    let mut text = String::with_capacity(name.len());
+    let mut origins = Vec::with_capacity(name.len());
+    for (i, c) in name.chars().enumerate() {
+        let latin = match deunicode::deunicode_char(c) {
+            Some(latin) => latin,
+            None => continue,
+        };
+        // Syllables of Chinese names are separated by spaces, which would get in the way of
+        // matching e.g. `beijing`
+        let latin = if c.is_ascii() { latin } else { latin.trim() };
+        for latin_char in latin.chars() {
+            text.push(latin_char);
+            origins.push(i);
+        }
+    }
+    Some(Transliteration { text, origins })
+}
+
+/// Transliteration isn't available without the `transliteration` feature.
+#[cfg(not(feature = "transliteration"))]
+pub(crate) fn transliterate(_name: &str) -> Option<Transliteration> {
+    None
+}
@@ -1,23 +1,281 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 //! Utility functions.
 
-use std::{fs, io};
+use std::{
+    cell::RefCell,
+    cmp::Reverse,
+    collections::HashMap,
+    env, fs,
+    io::{self, IsTerminal},
+    os::fd::AsFd,
+    path::{Path, PathBuf},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use crossterm::terminal;
-use fuzzy_matcher::clangd::fuzzy_indices;
+use fuzzy_matcher::{clangd::ClangdMatcher, skim::SkimMatcherV2, FuzzyMatcher};
+use nucleo_matcher::{
+    pattern::{Atom, AtomKind, CaseMatching, Normalization},
+    Utf32Str,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-use crate::{config::Config, state::State};
+use crate::{
+    config::{Config, Entry, Menu},
+    frame::Frame,
+    history,
+    layout::{Layout, LayoutOptions},
+    options::{Algorithm, CaseMode, MatchMode, MenuOrder, Options, PreviewPane},
+    state::State,
+    translit,
+};
 
-pub(crate) fn tty() -> io::Result<fs::File> {
-    fs::OpenOptions::new()
-        .read(false)
-        .write(true)
-        .open("/dev/tty")
+/// An entry paired with its fuzzy match score and matched character indices, if it matches the
+/// input. Fields: `(match, name, entry)`.
+pub(crate) type MatchedEntry = (Option<(i64, Vec<usize>)>, String, Entry);
+
+/// The terminal that the interface is drawn on, unless `--tty` is given.
+const TTY: &str = "/dev/tty";
+
+/// Opens the terminal that the interface is drawn on: the path given with `--tty`, or else the
+/// controlling terminal. If the controlling terminal can't be opened, a warning with the likely
+/// cause is printed, and the interface is drawn on stderr instead, as long as stdin and stderr are
+/// terminals.
+///
+/// Keys are read from stdin if it's a terminal, or else from the controlling terminal, and never
+/// from the terminal at `--tty`, so one of them must be available.
+pub(crate) fn tty(path: Option<&Path>) -> anyhow::Result<fs::File> {
+    let open = |path: &Path| fs::OpenOptions::new().read(false).write(true).open(path);
+    let keys_from_stdin = io::stdin().is_terminal();
+    if let Some(path) = path {
+        let tty =
+            open(path).map_err(|e| anyhow::anyhow!("Failed to open {}: {e}", path.display()))?;
+        if !keys_from_stdin {
+            if let Err(e) = fs::File::open(TTY) {
+                anyhow::bail!(
+                    "Failed to open {TTY} to read keys from: {e}. Keys are read from stdin if \
+                     it's a terminal, or else from {TTY}; `--tty` is only drawn on."
+                );
+            }
+        }
+        return Ok(tty);
+    }
+    let e = match open(Path::new(TTY)) {
+        Ok(tty) => return Ok(tty),
+        Err(e) => e,
+    };
+    let hint = tty_hint(&e);
+    if !keys_from_stdin {
+        anyhow::bail!(
+            "Failed to open {TTY}: {e}. {hint} Keys are read from {TTY} while stdin isn't a \
+             terminal, even with `--tty`."
+        );
+    }
+    if io::stderr().is_terminal() {
+        eprintln!("Warning: failed to open {TTY}: {e}. {hint} Drawing on stderr instead.");
+        let stderr = io::stderr().as_fd().try_clone_to_owned()?;
+        return Ok(fs::File::from(stderr));
+    }
+    anyhow::bail!("Failed to open {TTY}: {e}. {hint}")
+}
+
+/// Explains the likely cause of failing to open the controlling terminal, and what to do about it.
+fn tty_hint(e: &io::Error) -> &'static str {
+    // ENXIO, which is returned when the process has no controlling terminal
+    const NO_SUCH_DEVICE: i32 = 6;
+    match e.kind() {
+        io::ErrorKind::PermissionDenied => {
+            "Access to the terminal is denied, as in sandboxes such as Flatpak; allow the sandbox \
+             to access it (e.g. with `--device=all`), or pass `--tty <path>`."
+        }
+        io::ErrorKind::NotFound => {
+            "The terminal device doesn't exist, as in some containers; pass `--tty <path>`."
+        }
+        _ if e.raw_os_error() == Some(NO_SUCH_DEVICE) => {
+            "fr33zmenu isn't running in a terminal, e.g. it was started by a hotkey daemon; run \
+             it in a terminal emulator (see the example launcher script), or pass `--tty <path>`."
+        }
+        _ => "Pass `--tty <path>` to draw on another terminal.",
+    }
+}
+
+/// Returns the XDG base directory named by `var`, or `fallback` relative to the home directory if
+/// the variable is unset or empty.
+pub(crate) fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    match env::var_os(var) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)),
+    }
+}
+
+/// Expands a leading `~` in each word, and environment variables written as `$VAR` or `${VAR}`.
+/// Variables that aren't set are left as they are, so that they can still be expanded by a
+/// shell. `$$` is replaced with `$`.
+pub(crate) fn expand(s: &str) -> String {
+    let home = env::var("HOME").unwrap_or_default();
+    let mut expanded = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '~' if (i == 0 || s[..i].ends_with(char::is_whitespace))
+                && chars
+                    .peek()
+                    .is_none_or(|(_, c)| *c == '/' || c.is_whitespace()) =>
+            {
+                expanded.push_str(&home);
+            }
+            '$' => {
+                let rest = &s[i + 1..];
+                let (name, len) = if let Some(braced) = rest.strip_prefix('{') {
+                    match braced.find('}') {
+                        Some(end) => (&braced[..end], end + 2),
+                        None => ("", 0),
+                    }
+                } else {
+                    let end = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    (&rest[..end], end)
+                };
+
+                if rest.starts_with('$') {
+                    expanded.push('$');
+                    chars.next();
+                } else if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                    expanded.push('$');
+                } else if let Ok(value) = env::var(name) {
+                    expanded.push_str(&value);
+                    for _ in 0..len {
+                        chars.next();
+                    }
+                } else {
+                    expanded.push('$');
+                }
+            }
+            _ => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+/// Replaces the file at `path` atomically, by writing to a temporary file next to it and renaming
+/// it, so that a reader or another instance never sees the file partially written. The temporary
+/// file is removed if anything fails.
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut tmp_name = name.to_os_string();
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp = path.with_file_name(tmp_name);
+
+    let res = fs::write(&tmp, contents).and_then(|_| fs::rename(&tmp, path));
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    res
+}
+
+/// Quotes a string as a single shell word.
+pub(crate) fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Returns the number of terminal columns that text takes up, counting wide characters such as
+/// CJK and emoji as two columns, and combining characters as none.
+pub(crate) fn width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Returns the longest prefix of text that fits in `max_width` terminal columns, without splitting
+/// a character.
+pub(crate) fn truncate(text: &str, max_width: usize) -> &str {
+    if width(text) <= max_width {
+        return text;
+    }
+    // Each prefix is measured as a whole, since sequences such as emoji joined by zero-width
+    // joiners are narrower than the sum of their characters
+    for (i, c) in text.char_indices() {
+        if width(&text[..i + c.len_utf8()]) > max_width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// Expands a path with [expand].
+pub(crate) fn expand_path(path: &Path) -> PathBuf {
+    PathBuf::from(expand(&path.to_string_lossy()))
+}
+
+/// Returns the number of seconds since the Unix epoch.
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Converts a number of days since the Unix epoch into a `(year, month, day)` UTC date.
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
 }
 
+/// Matches `text` against a pattern where `*` matches any run of characters, and `?` matches any
+/// single character.
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last `*` consume one more character
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Sorts menus according to the `menu_order` option. Submenus are placed after all other menus,
+/// so that the tabs come first.
 pub(crate) fn sort_menus(config: &mut Config) {
+    let usage = match config.options.menu_order {
+        MenuOrder::Order => HashMap::new(),
+        MenuOrder::Usage => history::menu_counts(),
+    };
     config.menus.sort_by(|a, b| {
-        if a.1.order == b.1.order {
+        let a_usage = usage.get(&a.0).copied().unwrap_or_default();
+        let b_usage = usage.get(&b.0).copied().unwrap_or_default();
+        if a.1.submenu != b.1.submenu {
+            a.1.submenu.cmp(&b.1.submenu)
+        } else if a_usage != b_usage {
+            b_usage.cmp(&a_usage)
+        } else if a.1.order == b.1.order {
             a.0.to_lowercase().cmp(&b.0.to_lowercase())
         } else {
             a.1.order.cmp(&b.1.order)
@@ -25,56 +283,543 @@ pub(crate) fn sort_menus(config: &mut Config) {
     });
 }
 
-pub(crate) fn match_entries(
+/// A fuzzy matching algorithm.
+pub(crate) trait Matcher {
+    /// Matches `text` against the input, returning the score and the indices of the matched
+    /// characters, or `None` if it doesn't match. The indices count characters, not bytes.
+    fn indices(&self, text: &str, input: &str) -> Option<(i64, Vec<usize>)>;
+}
+
+impl Matcher for ClangdMatcher {
+    fn indices(&self, text: &str, input: &str) -> Option<(i64, Vec<usize>)> {
+        self.fuzzy_indices(text, input)
+    }
+}
+
+impl Matcher for SkimMatcherV2 {
+    fn indices(&self, text: &str, input: &str) -> Option<(i64, Vec<usize>)> {
+        self.fuzzy_indices(text, input)
+    }
+}
+
+/// Adapts nucleo's matcher, which needs to be borrowed mutably, and takes its pattern separately.
+struct NucleoMatcher {
+    matcher: RefCell<nucleo_matcher::Matcher>,
+    case: CaseMatching,
+}
+
+impl Matcher for NucleoMatcher {
+    fn indices(&self, text: &str, input: &str) -> Option<(i64, Vec<usize>)> {
+        let atom = Atom::new(
+            input,
+            self.case,
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+            false,
+        );
+        let mut buf = Vec::new();
+        let mut indices = Vec::new();
+        let haystack = Utf32Str::new(text, &mut buf);
+        let score = atom.indices(haystack, &mut self.matcher.borrow_mut(), &mut indices)?;
+        // The haystack holds the bytes of the text if every grapheme starts with an ASCII char,
+        // and otherwise the first char of each grapheme
+        let starts: Vec<usize> = match haystack {
+            Utf32Str::Ascii(_) => (0..text.len()).collect(),
+            Utf32Str::Unicode(_) => text.grapheme_indices(true).map(|(i, _)| i).collect(),
+        };
+        let mut indices: Vec<usize> = indices
+            .into_iter()
+            .filter_map(|i| starts.get(i as usize))
+            .map(|&start| text.char_indices().filter(|(i, _)| *i <= start).count() - 1)
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        Some((score.into(), indices))
+    }
+}
+
+/// Returns the matcher for the `algorithm` option, treating case as set by the `case` option.
+fn matcher(options: &Options) -> Box<dyn Matcher> {
+    match (options.algorithm, options.case) {
+        (Algorithm::Clangd, CaseMode::Insensitive) => {
+            Box::new(ClangdMatcher::default().ignore_case())
+        }
+        (Algorithm::Clangd, CaseMode::Smart) => Box::new(ClangdMatcher::default().smart_case()),
+        (Algorithm::Clangd, CaseMode::Sensitive) => {
+            Box::new(ClangdMatcher::default().respect_case())
+        }
+        (Algorithm::Skim, CaseMode::Insensitive) => {
+            Box::new(SkimMatcherV2::default().ignore_case())
+        }
+        (Algorithm::Skim, CaseMode::Smart) => Box::new(SkimMatcherV2::default().smart_case()),
+        (Algorithm::Skim, CaseMode::Sensitive) => Box::new(SkimMatcherV2::default().respect_case()),
+        (Algorithm::Nucleo, case) => Box::new(NucleoMatcher {
+            matcher: RefCell::new(nucleo_matcher::Matcher::default()),
+            case: match case {
+                CaseMode::Insensitive => CaseMatching::Ignore,
+                CaseMode::Smart => CaseMatching::Smart,
+                CaseMode::Sensitive => CaseMatching::Respect,
+            },
+        }),
+    }
+}
+
+/// Returns the match mode of a menu: the one chosen with the match mode keybind, or else the
+/// menu's own, or else the `match_mode` option.
+pub(crate) fn match_mode(state: &State, menu: &Menu, options: &Options) -> MatchMode {
+    state
+        .match_mode
+        .or(menu.match_mode)
+        .unwrap_or(options.match_mode)
+}
+
+/// Indicates that entry values are shown: as toggled with the toggle values keybind, or else as
+/// set by the menu's own `show_values`, or else the `show_values` option.
+pub(crate) fn show_values(state: &State, menu: &Menu, options: &Options) -> bool {
+    state
+        .show_values
+        .or(menu.show_values)
+        .unwrap_or(options.show_values)
+}
+
+/// Returns the command whose output is shown in the preview pane: the menu's own
+/// `preview_command`, or else the `preview_command` option. Returns `None` if it's empty.
+pub(crate) fn preview_command<'a>(menu: &'a Menu, options: &'a Options) -> Option<&'a str> {
+    let command = menu
+        .preview_command
+        .as_deref()
+        .unwrap_or(&options.preview_command);
+    Some(command).filter(|command| !command.trim().is_empty())
+}
+
+/// Returns the number of columns taken from the right of the entries, and the number of rows
+/// taken from below them, by the preview pane, including its border.
+pub(crate) fn preview_size(frame: &Frame, options: &Options) -> (u16, usize) {
+    let (width, height) = frame.size();
+    let percent = u32::from(options.preview_size.clamp(10, 90));
+    let part = |size: u16| (u32::from(size) * percent / 100) as u16;
+    match options.preview_pane {
+        PreviewPane::Off => (0, 0),
+        PreviewPane::Right => (part(width), 0),
+        PreviewPane::Bottom => (0, part(height).into()),
+    }
+}
+
+/// Returns the width that entries can be drawn in, beside the preview pane.
+pub(crate) fn entries_width(state: &State) -> u16 {
+    state.frame.size().0.saturating_sub(state.preview_width)
+}
+
+/// Matches `text` against the input without fuzzy matching, returning the indices of the matched
+/// characters. All matches are given the same score, so they keep their order.
+fn literal_indices(
+    text: &str,
     input: &str,
-    entries: &Vec<(String, String)>,
-) -> Vec<(Option<(i64, Vec<usize>)>, String, String)> {
-    let mut entries_sorted: Vec<(Option<(i64, Vec<usize>)>, String, String)> = entries
+    mode: MatchMode,
+    ignore_case: bool,
+) -> Option<(i64, Vec<usize>)> {
+    let fold = |c: char| -> char {
+        if ignore_case {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    if input.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let text: Vec<char> = text.chars().map(fold).collect();
+    let input: Vec<char> = input.chars().map(fold).collect();
+    let start = match mode {
+        MatchMode::Exact if text == input => Some(0),
+        MatchMode::Prefix if text.starts_with(&input) => Some(0),
+        MatchMode::Substring => text.windows(input.len()).position(|w| w == input),
+        _ => None,
+    }?;
+    Some((0, (start..start + input.len()).collect()))
+}
+
+/// The name of the menu of recently submitted entries.
+pub(crate) const RECENT_MENU: &str = "recent";
+
+/// Adds the menu of recently submitted entries as the first tab. Only entries that are still in
+/// the config are listed, most recent first. Nothing is added if the `recent_menu` option is
+/// zero, nothing has been submitted yet, or the config already has a menu with its name.
+pub(crate) fn insert_recent_menu(config: &mut Config) {
+    let count = config.options.recent_menu;
+    if count == 0 || config.menus.iter().any(|menu| menu.0 == RECENT_MENU) {
+        return;
+    }
+    let mut entries: Vec<(String, Entry)> = Vec::new();
+    for record in history::read().into_iter().rev() {
+        if entries.len() >= count {
+            break;
+        }
+        // Entries with the same name in different menus can't both be listed
+        if entries.iter().any(|entry| entry.0 == record.entry) {
+            continue;
+        }
+        let entry = config
+            .menus
+            .iter()
+            .find(|menu| menu.0 == record.menu)
+            .and_then(|menu| menu.1.entries.iter().find(|entry| entry.0 == record.entry));
+        if let Some((name, entry)) = entry {
+            let entry = Entry {
+                order: entries.len().try_into().unwrap_or(i64::MAX),
+                origin: Some(record.menu.clone()),
+                key: None,
+                ..entry.clone()
+            };
+            entries.push((name.clone(), entry));
+        }
+    }
+    if entries.is_empty() {
+        return;
+    }
+    let menu = Menu {
+        prompt: format!("{RECENT_MENU} -> "),
+        entries,
+        ..Default::default()
+    };
+    config.menus.insert(0, (RECENT_MENU.to_string(), menu));
+}
+
+/// Sets the frecency of every entry from the history, if the `frecency` option is set.
+pub(crate) fn assign_frecency(config: &mut Config) {
+    if !config.options.frecency {
+        return;
+    }
+    let scores = history::frecency();
+    for (menu_name, menu) in config.menus.iter_mut() {
+        for (name, entry) in menu.entries.iter_mut() {
+            let key = (menu_name.clone(), name.clone());
+            entry.frecency = scores.get(&key).copied().unwrap_or_default();
+        }
+    }
+}
+
+/// Returns the amount added to an entry's match score for its frecency. It grows slowly, so that
+/// frequently used entries are favored without drowning out the quality of the match.
+fn frecency_bonus(frecency: u64) -> i64 {
+    (10.0 * (1.0 + frecency as f64 / 1000.0).ln()) as i64
+}
+
+/// Matches a menu's entries against the input, and sorts them. Entries are left out until enough
+/// of the input has been typed to reach their `min_query`.
+pub(crate) fn match_entries(state: &State, menu: &Menu, options: &Options) -> Vec<MatchedEntry> {
+    let input = state.input.as_str();
+    let match_values = menu.match_values.unwrap_or(options.match_values);
+    let mode = match_mode(state, menu, options);
+    let matcher = matcher(options);
+    let ignore_case = match options.case {
+        CaseMode::Insensitive => true,
+        CaseMode::Smart => !input.chars().any(char::is_uppercase),
+        CaseMode::Sensitive => false,
+    };
+    let find = |text: &str| match mode {
+        MatchMode::Fuzzy => matcher.indices(text, input),
+        _ => literal_indices(text, input, mode, ignore_case),
+    };
+    let input_len = input.chars().count();
+    let preferred = preferred_entry(menu, input);
+    let mut entries_sorted: Vec<MatchedEntry> = menu
+        .entries
         .iter()
+        .filter(|entry| input_len >= entry.1.min_query.unwrap_or(menu.min_query))
+        // Separators and labels are only shown while the input is empty
+        .filter(|entry| input.is_empty() || entry.1.kind.is_submittable())
         .map(|entry| {
-            (
-                fuzzy_indices(&entry.0, input),
-                entry.0.clone(),
-                entry.1.clone(),
-            )
+            let name_match = find(&entry.0);
+            let translit_match = if options.transliterate {
+                translit::transliterate(&entry.0).and_then(|translit| {
+                    find(&translit.text).map(|(score, indices)| (score, translit.origins(&indices)))
+                })
+            } else {
+                None
+            };
+            let name_match = match (name_match, translit_match) {
+                (Some(name), Some(translit)) if translit.0 > name.0 => Some(translit),
+                (name, translit) => name.or(translit),
+            };
+            // Value matches don't highlight anything, since only the name is highlighted
+            let value_match = if match_values {
+                find(&entry.1.value).map(|(score, _)| (score, Vec::new()))
+            } else {
+                None
+            };
+            let best_match = match (name_match, value_match) {
+                (Some(name), Some(value)) if value.0 > name.0 => Some(value),
+                (name, value) => name.or(value),
+            }
+            .filter(|(score, _)| {
+                mode != MatchMode::Fuzzy || options.min_score == 0 || *score >= options.min_score
+            });
+            // Preferred entries are shown even if they don't match
+            let best_match = match preferred {
+                Some(preferred) if preferred == entry.0 => best_match.or(Some((0, Vec::new()))),
+                _ => best_match,
+            };
+            (best_match, entry.0.clone(), entry.1.clone())
         })
+        .filter(|entry| options.show_hidden || input.is_empty() || entry.0.is_some())
         .collect();
 
-    if input.is_empty() {
-        entries_sorted.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+    if input.is_empty() || mode != MatchMode::Fuzzy {
+        entries_sorted.sort_by_key(|a| {
+            (
+                a.0.is_none(),
+                Reverse(a.2.frecency),
+                a.2.order,
+                a.1.to_lowercase(),
+            )
+        });
     } else {
+        let key = |a: &MatchedEntry| {
+            a.0.as_ref()
+                .map(|(score, indices)| (score + frecency_bonus(a.2.frecency), indices.clone()))
+        };
         entries_sorted.sort_by(|a, b| {
             if a.0.is_none() && b.0.is_none() {
                 a.1.to_lowercase().cmp(&b.1.to_lowercase())
             } else {
-                b.0.cmp(&a.0)
+                key(b).cmp(&key(a))
             }
         });
     }
+    if let Some(index) =
+        preferred.and_then(|preferred| entries_sorted.iter().position(|entry| entry.1 == preferred))
+    {
+        entries_sorted[..=index].rotate_right(1);
+    }
 
     entries_sorted
 }
 
-pub(crate) fn count_selectable_entries(
-    state: &State,
-    entries: &Vec<(Option<(i64, Vec<usize>)>, String, String)>,
-) -> usize {
-    let h: usize = match terminal::size() {
-        Ok(size) => size.1.into(),
-        Err(_) => return 0,
-    };
+/// Returns the name of the entry that the menu's `prefer` rules rank first for the input. If the
+/// input starts with more than one of the queries, the longest one is used. Queries are matched
+/// regardless of case.
+fn preferred_entry<'a>(menu: &'a Menu, input: &str) -> Option<&'a str> {
+    if input.is_empty() {
+        return None;
+    }
+    let input = input.to_lowercase();
+    menu.prefer
+        .iter()
+        .filter(|(query, _)| input.starts_with(&query.to_lowercase()))
+        .max_by_key(|(query, _)| query.len())
+        .map(|(_, name)| name.as_str())
+}
+
+/// Returns the number of menus shown as tabs, which come before the submenus.
+pub(crate) fn count_tabs(config: &Config) -> usize {
+    config.menus.iter().filter(|menu| !menu.1.submenu).count()
+}
+
+/// Assigns a key to each submittable entry in a hotkey menu that doesn't have one, preferring the
+/// letters of the entry's name. Entries are assigned keys in the order they're shown in, and keys
+/// are never shared.
+pub(crate) fn assign_hotkeys(menu: &mut Menu) {
+    if !menu.hotkeys {
+        return;
+    }
+    let mut taken: Vec<char> = menu
+        .entries
+        .iter()
+        .filter_map(|(_, entry)| entry.key)
+        .collect();
+
+    let mut unassigned: Vec<&mut (String, Entry)> = menu
+        .entries
+        .iter_mut()
+        .filter(|(_, entry)| entry.kind.is_submittable() && !entry.info && entry.key.is_none())
+        .collect();
+    unassigned.sort_by_key(|(name, entry)| (entry.order, name.to_lowercase()));
 
-    let count = if state.input.is_empty() {
-        entries.len()
+    for (name, entry) in unassigned {
+        let key = name
+            .to_lowercase()
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .chain('a'..='z')
+            .chain('0'..='9')
+            .find(|c| !taken.contains(c));
+        if let Some(key) = key {
+            taken.push(key);
+            entry.key = Some(key);
+        }
+    }
+}
+
+/// Returns the index of the entry bound to a hotkey, among the selectable entries. Hotkeys are
+/// only active while the input is empty.
+pub(crate) fn hotkey_entry(state: &State, entries: &[MatchedEntry], key: char) -> Option<usize> {
+    if !state.input.is_empty() {
+        return None;
+    }
+    entries
+        .iter()
+        .filter(|entry| is_selectable(state, entry))
+        .position(|entry| entry.2.key == Some(key) && !entry.2.info)
+}
+
+/// Indicates that the entry cursor can be placed on the entry.
+pub(crate) fn is_selectable(state: &State, entry: &MatchedEntry) -> bool {
+    entry.2.kind.is_submittable() && (state.input.is_empty() || entry.0.is_some())
+}
+
+/// Returns the number of entries that match the input out of all the entries that can be
+/// submitted, like `[5/87]`, for the `match_count` option.
+pub(crate) fn match_count(state: &State, entries: &[MatchedEntry]) -> String {
+    let total = entries
+        .iter()
+        .filter(|entry| entry.2.kind.is_submittable())
+        .count();
+    format!("[{}/{total}]", count_selectable_entries(state, entries))
+}
+
+fn count_selectable_entries(state: &State, entries: &[MatchedEntry]) -> usize {
+    entries
+        .iter()
+        .filter(|entry| is_selectable(state, entry))
+        .count()
+}
+
+/// Returns the number of rows that entries can be drawn in. The rows above them hold the menu
+/// line and the prompt, and the row below them is reserved for the overflow indicator.
+fn count_entry_rows(state: &State, options: &LayoutOptions) -> usize {
+    let rows = options.rows();
+    usize::from(state.frame.size().1)
+        .saturating_sub(usize::from(rows.footer))
+        .saturating_sub(state.scrollback_rows)
+        .saturating_sub(state.preview_rows)
+        .saturating_sub(usize::from(rows.entries) + 1)
+}
+
+/// Fits the entry cursor and the scrolling to the matching entries, and to the rows that they're
+/// drawn in. This is the only place they're updated after the entries change, so that the entry
+/// cursor can always reach every selectable entry, and the selected entry is always visible.
+pub(crate) fn update_viewport(
+    state: &mut State,
+    entries: &[MatchedEntry],
+    layout: &Layout,
+    options: &LayoutOptions,
+) {
+    state.entry_count = count_selectable_entries(state, entries);
+    state.entry_rows = count_entry_rows(state, options);
+    state.entry_columns = layout.per_row;
+
+    // The entries can change while the menu is open, e.g. when its source is refreshed
+    if state.entry_count == 0 {
+        state.entry_cursor = false;
+        state.entry_index = 0;
     } else {
-        entries
-            .iter()
-            .filter_map(|e| match &e.0 {
-                Some(e) => Some(e),
-                None => None,
+        state.entry_index = state.entry_index.min(state.entry_count - 1);
+    }
+    scroll_to_selection(state, entries, layout);
+}
+
+/// Scrolls the entries so that the row of the selected entry is visible. The entries are
+/// scrolled back to the top while the entry cursor is hidden.
+fn scroll_to_selection(state: &mut State, entries: &[MatchedEntry], layout: &Layout) {
+    if !state.entry_cursor || state.entry_rows == 0 {
+        state.entry_scroll = 0;
+        return;
+    }
+    let row = layout.selected_row(state, entries);
+    if row < state.entry_scroll {
+        state.entry_scroll = row;
+    } else if row >= state.entry_scroll + state.entry_rows {
+        state.entry_scroll = row + 1 - state.entry_rows;
+    }
+}
+
+/// Returns the entry under the entry cursor. The entry index only counts selectable entries.
+pub(crate) fn selected_entry<'a>(
+    state: &State,
+    entries: &'a [MatchedEntry],
+) -> Option<&'a MatchedEntry> {
+    entries
+        .iter()
+        .filter(|entry| is_selectable(state, entry))
+        .nth(state.entry_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn width_counts_terminal_columns() {
+        assert_eq!(width("abc"), 3);
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("🦀"), 2);
+        assert_eq!(width("🇯🇵"), 2);
+        // Combining marks take no column of their own
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("\u{301}"), 0);
+        // Zero-width joiners combine emoji into a single glyph
+        assert_eq!(width("👩\u{200d}🔬"), 2);
+        assert_eq!(width("👨\u{200d}👩\u{200d}👧"), 2);
+    }
+
+    #[test]
+    fn truncate_keeps_wide_characters_whole() {
+        assert_eq!(truncate("日本語", 6), "日本語");
+        assert_eq!(truncate("日本語", 5), "日本");
+        assert_eq!(truncate("日本語", 1), "");
+        assert_eq!(truncate("a🦀b", 2), "a");
+        assert_eq!(truncate("a🦀b", 3), "a🦀");
+    }
+
+    #[test]
+    fn truncate_keeps_sequences_together() {
+        assert_eq!(truncate("e\u{301}x", 1), "e\u{301}");
+        assert_eq!(truncate("👩\u{200d}🔬x", 2), "👩\u{200d}🔬");
+        assert_eq!(truncate("👩\u{200d}🔬x", 1), "");
+        let family = "👨\u{200d}👩\u{200d}👧 family";
+        assert_eq!(truncate(family, 3), "👨\u{200d}👩\u{200d}👧 ");
+        assert!(width(truncate(family, 3)) <= 3);
+    }
+
+    /// Matches the input against a single entry name with each algorithm, returning the
+    /// highlighted characters of the name.
+    fn highlighted(name: &str, input: &str) -> Vec<String> {
+        let mut options = crate::config::load_config(None, None).unwrap().options;
+        let menu = Menu {
+            entries: vec![(name.to_string(), Entry::from("-".to_string()))],
+            ..Default::default()
+        };
+        let state = State {
+            input: input.to_string(),
+            ..Default::default()
+        };
+        [Algorithm::Clangd, Algorithm::Skim, Algorithm::Nucleo]
+            .into_iter()
+            .map(|algorithm| {
+                options.algorithm = algorithm;
+                let entries = match_entries(&state, &menu, &options);
+                let (_, indices) = entries[0].0.clone().expect("the name should match");
+                name.chars()
+                    .enumerate()
+                    .filter(|(i, _)| indices.contains(i))
+                    .map(|(_, c)| c)
+                    .collect()
             })
-            .count()
-    };
+            .collect()
+    }
 
-    usize::min(count, h.saturating_sub(5))
+    #[test]
+    fn match_entries_highlights_wide_characters() {
+        assert_eq!(highlighted("日本語のメニュー", "本メ"), ["本メ"; 3]);
+        assert_eq!(highlighted("🦀 crab rust", "rust"), ["rust"; 3]);
+        assert_eq!(highlighted("🇯🇵 japan", "jp"), ["jp"; 3]);
+    }
+
+    #[test]
+    fn match_entries_highlights_sequences() {
+        assert_eq!(highlighted("👩\u{200d}🔬 lab", "lab"), ["lab"; 3]);
+        assert_eq!(highlighted("cafe\u{301} bar", "bar"), ["bar"; 3]);
+        assert_eq!(highlighted("e\u{301}日本 go", "go"), ["go"; 3]);
+    }
 }
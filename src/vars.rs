@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Substitutes the variables defined in the `[vars]` table of the config, written as
+//! `{vars.name}`, into every other string and key of the config.
+
+use std::collections::HashMap;
+
+use ::config::{Value, ValueKind};
+use anyhow::{anyhow, bail, Result};
+
+/// Marks the start of a variable reference.
+const PREFIX: &str = "{vars.";
+
+/// Replaces the variable references in the config, after resolving the references between the
+/// variables themselves. Fails if a variable isn't defined, or if variables refer to each other
+/// in a cycle.
+pub(crate) fn substitute(config: &mut ::config::Config) -> Result<()> {
+    let ValueKind::Table(root) = &mut config.cache.kind else {
+        return Ok(());
+    };
+    let defined: HashMap<String, String> = match root.get("vars") {
+        Some(vars) => vars
+            .clone()
+            .into_table()
+            .map_err(|e| anyhow!("vars: {e}"))?
+            .into_iter()
+            .map(|(name, value)| Ok((name, value.into_string()?)))
+            .collect::<Result<_, ::config::ConfigError>>()
+            .map_err(|e| anyhow!("vars: {e}"))?,
+        None => HashMap::new(),
+    };
+
+    let mut resolved = HashMap::new();
+    for name in defined.keys() {
+        resolve(name, &defined, &mut resolved, &mut Vec::new())?;
+    }
+    for (key, value) in root.iter_mut() {
+        if key != "vars" {
+            substitute_value(value, &resolved)?;
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a variable, and the variables it refers to. `stack` holds the variables being
+/// resolved, to detect cycles.
+fn resolve(
+    name: &str,
+    defined: &HashMap<String, String>,
+    resolved: &mut HashMap<String, String>,
+    stack: &mut Vec<String>,
+) -> Result<String> {
+    if let Some(value) = resolved.get(name) {
+        return Ok(value.clone());
+    }
+    if stack.iter().any(|other| other == name) {
+        stack.push(name.to_string());
+        bail!(
+            "vars: variables refer to each other in a cycle: {}",
+            stack.join(" -> ")
+        );
+    }
+    let value = defined
+        .get(name)
+        .ok_or_else(|| anyhow!("vars: variable '{name}' isn't defined"))?;
+
+    stack.push(name.to_string());
+    let mut result = String::with_capacity(value.len());
+    for part in parts(value) {
+        match part {
+            Part::Text(text) => result.push_str(text),
+            Part::Var(var) => result.push_str(&resolve(var, defined, resolved, stack)?),
+        }
+    }
+    stack.pop();
+
+    resolved.insert(name.to_string(), result.clone());
+    Ok(result)
+}
+
+/// Substitutes the variables into a value, and into the keys and values of a table or array.
+fn substitute_value(value: &mut Value, vars: &HashMap<String, String>) -> Result<()> {
+    match &mut value.kind {
+        ValueKind::String(s) => *s = substitute_str(s, vars)?,
+        ValueKind::Array(array) => {
+            for value in array.iter_mut() {
+                substitute_value(value, vars)?;
+            }
+        }
+        ValueKind::Table(table) => {
+            let entries: Vec<(String, Value)> = table.drain().collect();
+            for (key, mut value) in entries {
+                substitute_value(&mut value, vars)?;
+                table.insert(substitute_str(&key, vars)?, value);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Substitutes the resolved variables into a string.
+fn substitute_str(s: &str, vars: &HashMap<String, String>) -> Result<String> {
+    if !s.contains(PREFIX) {
+        return Ok(s.to_string());
+    }
+    let mut result = String::with_capacity(s.len());
+    for part in parts(s) {
+        match part {
+            Part::Text(text) => result.push_str(text),
+            Part::Var(var) => result.push_str(
+                vars.get(var)
+                    .ok_or_else(|| anyhow!("vars: variable '{var}' isn't defined"))?,
+            ),
+        }
+    }
+    Ok(result)
+}
+
+/// A piece of a string with variable references.
+enum Part<'a> {
+    Text(&'a str),
+    Var(&'a str),
+}
+
+/// Splits a string into text and variable references. A `{vars.` without a valid name and a
+/// closing brace is kept as text.
+fn parts(s: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find(PREFIX) {
+        let after = &rest[start + PREFIX.len()..];
+        let name_len = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(after.len());
+        if name_len == 0 || !after[name_len..].starts_with('}') {
+            parts.push(Part::Text(&rest[..start + PREFIX.len()]));
+            rest = after;
+            continue;
+        }
+        parts.push(Part::Text(&rest[..start]));
+        parts.push(Part::Var(&after[..name_len]));
+        rest = &after[name_len + 1..];
+    }
+    parts.push(Part::Text(rest));
+    parts
+}
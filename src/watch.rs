@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+//! Watches config files for changes.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+
+use crate::config;
+
+/// Watches the files that a config path refers to. See [config::config_files].
+pub(crate) struct Watcher {
+    /// The watched config path.
+    path: PathBuf,
+
+    /// Keeps watching until dropped.
+    _watcher: RecommendedWatcher,
+
+    /// Receives file system events in the watched directory.
+    events: Receiver<notify::Result<Event>>,
+}
+
+impl Watcher {
+    /// Starts watching the config at `path`.
+    ///
+    /// The directory holding the config files is watched, rather than the files themselves, so
+    /// that files which are replaced (as many editors do when saving) keep being watched.
+    pub(crate) fn new(path: &Path) -> Result<Self> {
+        // Events are reported with absolute paths, which are compared with the config files
+        let path = env::current_dir()
+            .context("Failed to get the current directory")?
+            .join(path);
+        let dir = if path.is_dir() {
+            &path
+        } else {
+            path.parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or_else(|| Path::new("."))
+        };
+        let (sender, events) = channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed to start watching the config")?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch config directory '{}'", dir.display()))?;
+        Ok(Self {
+            path,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Returns `true` if any config file changed since the last call.
+    pub(crate) fn changed(&self) -> bool {
+        let paths: Vec<PathBuf> = self
+            .events
+            .try_iter()
+            .filter_map(|event| event.ok())
+            .filter(|event| !event.kind.is_access())
+            .flat_map(|event| event.paths)
+            .collect();
+        if paths.is_empty() {
+            return false;
+        }
+        let files = config::config_files(&self.path).unwrap_or_default();
+        paths.iter().any(|path| files.contains(path))
+    }
+
+    /// The watched config path.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}